@@ -0,0 +1,35 @@
+//! Count kills across every event sent by Dota 2, using state shared between handler calls.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dota::{components::GameState, GSIServer};
+
+/// The highest total score of both teams seen so far.
+#[derive(Default)]
+struct KillCounter {
+    total: AtomicU64,
+}
+
+async fn count_kills(counter: Arc<KillCounter>, gs: GameState) {
+    let summary = gs.to_summary_json();
+    let score = |key| summary[key].as_u64().unwrap_or(0);
+    let total = score("radiant_score") + score("dire_score");
+
+    // Events may be handled out of order, so only ever move the count forward.
+    let previous = counter.total.fetch_max(total, Ordering::SeqCst);
+    if total > previous {
+        println!("{} new kills, {} in total", total - previous, total);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+
+    let counter = Arc::new(KillCounter::default());
+    GSIServer::new("127.0.0.1:3000")
+        .run_with(counter, count_kills)
+        .await?;
+
+    Ok(())
+}
@@ -1,15 +1,34 @@
 use clap::Parser;
+use serde::Serialize;
 
 use dota::{components::GameState, GSIServer};
 
 /// Echo back Dota GameState integration state.
-async fn echo_gamestate_handler(gs: GameState) {
-    println!("{}", gs);
+/// When json is set, echo the parsed state as JSON instead of its summary.
+async fn echo_gamestate_handler(gs: GameState, json: bool, pretty: bool) {
+    if json {
+        echo_as_json(&gs, pretty);
+    } else {
+        println!("{}", gs);
+    }
 }
 
 /// Echo back raw JSON events.
-async fn echo_json_handler(value: serde_json::Value) {
-    println!("{}", value);
+async fn echo_json_handler(value: serde_json::Value, pretty: bool) {
+    echo_as_json(&value, pretty);
+}
+
+fn echo_as_json<T: Serialize>(value: &T, pretty: bool) {
+    let serialized = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+
+    match serialized {
+        Ok(s) => println!("{}", s),
+        Err(e) => log::error!("failed to serialize event: {}", e),
+    }
 }
 
 /// Listen for Dota 2 events and echo (slam) them.
@@ -18,13 +37,38 @@ async fn echo_json_handler(value: serde_json::Value) {
 struct Args {
     /// URI for the server to listen for events.
     /// This must be the same URI used in the Game State configuration file.
+    /// Defaults to 127.0.0.1:3000.
     #[arg(short, long)]
-    uri: String,
+    uri: Option<String>,
+
+    /// Port for the server to listen for events on 127.0.0.1, instead of a full URI.
+    #[arg(long, conflicts_with = "uri")]
+    port: Option<u16>,
 
     /// Don't attempt to parse JSON data.
     /// Echo raw JSON events as received from Dota 2.
     #[arg(short, long)]
     raw: bool,
+
+    /// Echo the parsed GameState as JSON instead of a summary.
+    /// Has no effect with --raw, as raw events are always echoed as JSON.
+    #[arg(short, long)]
+    json: bool,
+
+    /// Pretty print JSON output, instead of one compact line per event.
+    #[arg(short, long)]
+    pretty: bool,
+}
+
+impl Args {
+    /// Listen on --uri, or on --port of localhost, or on the default 127.0.0.1:3000.
+    fn server(&self) -> GSIServer {
+        match (&self.uri, self.port) {
+            (Some(uri), _) => GSIServer::new(uri),
+            (None, Some(port)) => GSIServer::new(&format!("127.0.0.1:{}", port)),
+            (None, None) => GSIServer::default(),
+        }
+    }
 }
 
 #[tokio::main]
@@ -33,12 +77,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
-    let server = GSIServer::new(&args.uri);
+    let server = args.server();
+
+    let (json, pretty) = (args.json, args.pretty);
 
     if args.raw {
-        server.run(echo_json_handler).await?;
+        server
+            .run(move |value| echo_json_handler(value, pretty))
+            .await?;
     } else {
-        server.run(echo_gamestate_handler).await?;
+        server
+            .run(move |gs| echo_gamestate_handler(gs, json, pretty))
+            .await?;
     }
 
     Ok(())
@@ -1,11 +1,17 @@
+use std::io;
 use std::path::PathBuf;
 
 use async_trait::async_trait;
 use clap::Parser;
-use tokio::fs::File;
+use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
 
-use dota::{GSIServer, GameStateHandler};
+use dota::handlers::JsonLinesHandler;
+use dota::replay::{recording_file_name, RECORDING_TIMESTAMP_FORMAT};
+use dota::{GSIServer, GameStateHandler, HandlerError};
+
+/// Field added to each event written with --single-file, holding the time it was received.
+const RECORDED_AT_FIELD: &str = "recorded_at";
 
 #[derive(Clone, Debug)]
 struct RecallHandler {
@@ -15,19 +21,54 @@ struct RecallHandler {
 #[async_trait]
 impl GameStateHandler<serde_json::Value> for RecallHandler {
     /// Save raw GameState Integration as JSON for later recalling
-    async fn handle(self, gs: serde_json::Value) {
-        let file_name = format!("DotaGSI_{}.json", chrono::offset::Local::now());
-        let mut file_path = self.output_dir.clone();
-        file_path.push(file_name);
-
-        let json_str = serde_json::to_string(&gs).expect("Unable to cast to JSON string.");
-
-        let mut file = File::create(file_path)
-            .await
-            .expect("Failed to create file for DotaGSI JSON.");
-        file.write_all(json_str.as_bytes())
-            .await
-            .expect("Failed to write DotaGSI JSON file.");
+    async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+        let timestamp = chrono::offset::Utc::now().format(RECORDING_TIMESTAMP_FORMAT);
+        let json_str = serde_json::to_string(&gs)?;
+
+        // Events received in the same millisecond would share a name, so count up until a
+        // name is free. Creating the file only if it doesn't exist makes this safe across tasks.
+        let mut counter = 0;
+        let mut file = loop {
+            let file_path = self
+                .output_dir
+                .join(recording_file_name(&timestamp, counter));
+
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(file_path)
+                .await
+            {
+                Ok(file) => break file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        file.write_all(json_str.as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+/// Append every event as a JSON line to a single file, with the time it was received.
+#[derive(Clone, Debug)]
+struct SingleFileHandler {
+    inner: JsonLinesHandler,
+}
+
+#[async_trait]
+impl GameStateHandler<serde_json::Value> for SingleFileHandler {
+    async fn handle(self, mut gs: serde_json::Value) -> Result<(), HandlerError> {
+        if let Some(event) = gs.as_object_mut() {
+            event.insert(
+                RECORDED_AT_FIELD.to_owned(),
+                chrono::offset::Local::now().to_rfc3339().into(),
+            );
+        }
+
+        self.inner.write_line(&gs).await?;
+
+        Ok(())
     }
 }
 
@@ -37,11 +78,32 @@ impl GameStateHandler<serde_json::Value> for RecallHandler {
 struct Args {
     /// URI for the server to listen for events.
     /// This must be the same URI used in the Game State configuration file.
-    uri: String,
+    /// Defaults to 127.0.0.1:3000.
+    uri: Option<String>,
+
+    /// Port for the server to listen for events on 127.0.0.1, instead of a full URI.
+    #[arg(long, conflicts_with = "uri")]
+    port: Option<u16>,
 
-    /// Optional directory where to store JSON event files.
+    /// Optional directory where to store JSON event files, one file per event.
     #[arg(short, long)]
     output_dir: Option<PathBuf>,
+
+    /// Append every event as a JSON line to this file, instead of one file per event.
+    /// Each line includes the time the event was received in a `recorded_at` field.
+    #[arg(long, conflicts_with = "output_dir")]
+    single_file: Option<PathBuf>,
+}
+
+impl Args {
+    /// Listen on --uri, or on --port of localhost, or on the default 127.0.0.1:3000.
+    fn server(&self) -> GSIServer {
+        match (&self.uri, self.port) {
+            (Some(uri), _) => GSIServer::new(uri),
+            (None, Some(port)) => GSIServer::new(&format!("127.0.0.1:{}", port)),
+            (None, None) => GSIServer::default(),
+        }
+    }
 }
 
 #[tokio::main]
@@ -49,6 +111,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
 
     let args = Args::parse();
+    let server = args.server();
+
+    if let Some(path) = args.single_file {
+        let handler = SingleFileHandler {
+            inner: JsonLinesHandler::open(path).await?,
+        };
+        server.run_with_handler(handler).await?;
+
+        return Ok(());
+    }
+
     let output_dir = match args.output_dir {
         Some(p) => p,
         None => {
@@ -60,7 +133,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         output_dir: output_dir.clone(),
     };
 
-    let server = GSIServer::new(&args.uri);
     server.run_with_handler(handler).await?;
 
     Ok(())
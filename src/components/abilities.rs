@@ -1,18 +1,69 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 
 use serde::{de, de::Error, ser, Deserialize, Serialize};
 use thiserror;
 
-use super::{PlayerID, Team};
+use super::{title_case, PlayerID, Team};
+
+/// Prefix of the abilities of Dota Plus, like `plus_high_five`.
+const DOTA_PLUS_PREFIX: &str = "plus_";
+
+/// Heroes whose abilities are prefixed by more than one word, like `skeleton_king_`.
+/// Every other ability is prefixed by a single word, like `axe_` or `plus_`.
+const MULTI_WORD_ABILITY_PREFIXES: &[&str] = &[
+    "abyssal_underlord_",
+    "ancient_apparition_",
+    "arc_warden_",
+    "bounty_hunter_",
+    "chaos_knight_",
+    "crystal_maiden_",
+    "dark_seer_",
+    "dark_willow_",
+    "death_prophet_",
+    "doom_bringer_",
+    "dragon_knight_",
+    "drow_ranger_",
+    "earth_spirit_",
+    "elder_titan_",
+    "ember_spirit_",
+    "faceless_void_",
+    "keeper_of_the_light_",
+    "legion_commander_",
+    "life_stealer_",
+    "lone_druid_",
+    "monkey_king_",
+    "naga_siren_",
+    "night_stalker_",
+    "nyx_assassin_",
+    "obsidian_destroyer_",
+    "ogre_magi_",
+    "phantom_assassin_",
+    "phantom_lancer_",
+    "primal_beast_",
+    "shadow_demon_",
+    "shadow_shaman_",
+    "skeleton_king_",
+    "skywrath_mage_",
+    "spirit_breaker_",
+    "storm_spirit_",
+    "templar_assassin_",
+    "troll_warlord_",
+    "void_spirit_",
+    "winter_wyvern_",
+    "witch_doctor_",
+];
 
 #[derive(thiserror::Error, Debug)]
 pub enum AbilitiesError {
     #[error("failed to parse ability ID number in `{0}`")]
     ParseIDError(String),
+    #[error("too many abilities to assign IDs to: {0}")]
+    TooManyAbilities(usize),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Ability {
     name: String,
     level: u8,
@@ -21,6 +72,58 @@ pub struct Ability {
     ability_active: bool,
     cooldown: u16,
     ultimate: bool,
+    charges: Option<u16>,
+    max_charges: Option<u16>,
+    charge_restore_time: Option<u16>,
+}
+
+impl Ability {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A readable name without the hero prefix, like `Hellfire Blast` for
+    /// `skeleton_king_hellfire_blast`.
+    pub fn display_name(&self) -> String {
+        let name = MULTI_WORD_ABILITY_PREFIXES
+            .iter()
+            .find_map(|prefix| self.name.strip_prefix(prefix))
+            .or_else(|| self.name.split_once('_').map(|(_, name)| name))
+            .unwrap_or(&self.name);
+
+        title_case(name)
+    }
+
+    /// Whether this is a Dota Plus ability, like `plus_high_five`, instead of a hero ability.
+    pub fn is_dota_plus(&self) -> bool {
+        self.name.starts_with(DOTA_PLUS_PREFIX)
+    }
+
+    /// The remaining cooldown, or zero when the ability is not on cooldown.
+    pub fn cooldown_duration(&self) -> Duration {
+        Duration::from_secs(self.cooldown as u64)
+    }
+
+    pub fn is_on_cooldown(&self) -> bool {
+        self.cooldown > 0
+    }
+
+    /// Charges ready to be cast, for charge-based abilities like Shrapnel.
+    /// None for abilities without charges.
+    pub fn charges_ready(&self) -> Option<u16> {
+        self.charges
+    }
+
+    /// Maximum charges this ability can hold, for charge-based abilities.
+    pub fn max_charges(&self) -> Option<u16> {
+        self.max_charges
+    }
+
+    /// The remaining time until the next charge is restored, for charge-based abilities.
+    pub fn charge_restore_duration(&self) -> Option<Duration> {
+        self.charge_restore_time
+            .map(|seconds| Duration::from_secs(u64::from(seconds)))
+    }
 }
 
 impl fmt::Display for Ability {
@@ -40,7 +143,7 @@ impl fmt::Display for Ability {
     }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
 pub struct AbilityID(u8);
 
 impl<'de> Deserialize<'de> for AbilityID {
@@ -51,8 +154,11 @@ impl<'de> Deserialize<'de> for AbilityID {
         let s = String::deserialize(deserializer)?;
         let mut slot_split = s.split("ability").map(|s| s.parse::<u8>());
 
-        if let (_, Some(index)) = (slot_split.next(), slot_split.next()) {
-            return Ok(AbilityID(index.expect("failed to parse ID")));
+        if let (_, Some(index_res)) = (slot_split.next(), slot_split.next()) {
+            let index = index_res
+                .map_err(|_| AbilitiesError::ParseIDError(s.clone()))
+                .map_err(D::Error::custom)?;
+            return Ok(AbilityID(index));
         }
 
         Err(D::Error::custom(AbilitiesError::ParseIDError(s)))
@@ -68,19 +174,121 @@ impl Serialize for AbilityID {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+/// Abilities of a single hero.
+/// Dota sends an object keyed by `abilityN`, but some custom games send an array instead.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeroAbilities {
+    Keyed(HashMap<AbilityID, Ability>),
+    Sequential(Vec<Ability>),
+}
+
+impl HeroAbilities {
+    /// Key the abilities by their ID, numbering abilities sent as an array by their position.
+    fn into_keyed(self) -> Result<HashMap<AbilityID, Ability>, AbilitiesError> {
+        match self {
+            HeroAbilities::Keyed(abilities) => Ok(abilities),
+            HeroAbilities::Sequential(abilities) => abilities
+                .into_iter()
+                .enumerate()
+                .map(|(index, ability)| match u8::try_from(index) {
+                    Ok(index) => Ok((AbilityID(index), ability)),
+                    Err(_) => Err(AbilitiesError::TooManyAbilities(index + 1)),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GameAbilitiesRepr {
+    Spectating(HashMap<Team, HashMap<PlayerID, HeroAbilities>>),
+    Playing(HeroAbilities),
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum GameAbilities {
     Spectating(HashMap<Team, HashMap<PlayerID, HashMap<AbilityID, Ability>>>),
     Playing(HashMap<AbilityID, Ability>),
 }
 
+impl<'de> Deserialize<'de> for GameAbilities {
+    fn deserialize<D>(deserializer: D) -> Result<GameAbilities, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match GameAbilitiesRepr::deserialize(deserializer)? {
+            GameAbilitiesRepr::Playing(abilities) => abilities
+                .into_keyed()
+                .map(GameAbilities::Playing)
+                .map_err(D::Error::custom),
+            GameAbilitiesRepr::Spectating(teams) => {
+                let mut keyed = HashMap::with_capacity(teams.len());
+
+                for (team, players) in teams {
+                    let mut team_abilities = HashMap::with_capacity(players.len());
+
+                    for (id, abilities) in players {
+                        let abilities = abilities.into_keyed().map_err(D::Error::custom)?;
+                        team_abilities.insert(id, abilities);
+                    }
+
+                    keyed.insert(team, team_abilities);
+                }
+
+                Ok(GameAbilities::Spectating(keyed))
+            }
+        }
+    }
+}
+
+impl GameAbilities {
+    /// The ultimate ability of the hero being played.
+    /// Returns None when spectating, as abilities are sent for every player.
+    pub fn ultimate(&self) -> Option<&Ability> {
+        match self {
+            GameAbilities::Playing(abilities) => abilities.values().find(|a| a.ultimate),
+            GameAbilities::Spectating(_) => None,
+        }
+    }
+
+    /// Get the ability in the given slot, sent by Dota as `abilityN`.
+    pub fn get(&self, slot: u8) -> Option<&Ability> {
+        match self {
+            GameAbilities::Playing(abilities) => abilities.get(&AbilityID(slot)),
+            GameAbilities::Spectating(_) => None,
+        }
+    }
+
+    /// Abilities of the hero being played sorted by their slot, without Dota Plus abilities.
+    pub fn gameplay_abilities(&self) -> Vec<&Ability> {
+        self.abilities_in_order()
+            .into_iter()
+            .filter(|a| !a.is_dota_plus())
+            .collect()
+    }
+
+    /// All abilities of the hero being played, sorted by their slot.
+    pub fn abilities_in_order(&self) -> Vec<&Ability> {
+        match self {
+            GameAbilities::Playing(abilities) => {
+                let mut sorted: Vec<(&AbilityID, &Ability)> = abilities.iter().collect();
+                sorted.sort_by_key(|(id, _)| id.0);
+                sorted.into_iter().map(|(_, a)| a).collect()
+            }
+            GameAbilities::Spectating(_) => Vec::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_abilities_deserialize() {
+    fn test_game_abilities_deserialize_array() {
         let json_str = r#"[{
           "ability_active": true,
           "can_cast": true,
@@ -136,17 +344,209 @@ mod tests {
           "ultimate": false
         }
       ]"#;
-        let abilities: Vec<Ability> =
-            serde_json::from_str(json_str).expect("Failed to deserialize Abilities");
+        let game_abilities: GameAbilities =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameAbilities");
+        let abilities = game_abilities.abilities_in_order();
 
+        assert!(matches!(game_abilities, GameAbilities::Playing(_)));
         assert_eq!(abilities.len(), 6);
+        assert_eq!(
+            game_abilities.get(3).map(|a| a.name()),
+            Some("marci_unleash")
+        );
+        assert_eq!(
+            game_abilities.ultimate().map(|a| a.name()),
+            Some("marci_unleash")
+        );
         assert!(abilities.iter().all(|a| a.ability_active));
         assert!(abilities.iter().all(|a| a.can_cast));
+        assert!(abilities.iter().any(|a| a.name == "plus_guild_banner"));
+        assert!(abilities.iter().any(|a| a.name == "marci_unleash"));
+        assert!(abilities.iter().all(|a| !a.is_on_cooldown()));
+        assert_eq!(
+            abilities
+                .iter()
+                .filter(|a| a.is_dota_plus())
+                .map(|a| a.display_name())
+                .collect::<Vec<String>>(),
+            vec!["High Five", "Guild Banner"]
+        );
+        assert_eq!(abilities[1].display_name(), "Companion Run");
+    }
+
+    #[test]
+    fn test_ability_cooldown_duration() {
+        let json_str = r#"{
+          "ability_active": true,
+          "can_cast": false,
+          "cooldown": 12,
+          "level": 2,
+          "name": "marci_grapple",
+          "passive": false,
+          "ultimate": false
+        }"#;
+        let ability: Ability =
+            serde_json::from_str(json_str).expect("Failed to deserialize Ability");
+
+        assert!(ability.is_on_cooldown());
+        assert_eq!(ability.cooldown_duration(), Duration::from_secs(12));
+        assert_eq!(ability.charges_ready(), None);
+        assert_eq!(ability.max_charges(), None);
+        assert_eq!(ability.charge_restore_duration(), None);
+    }
+
+    #[test]
+    fn test_ability_charges() {
+        let json_str = r#"{
+          "ability_active": true,
+          "can_cast": true,
+          "cooldown": 0,
+          "level": 3,
+          "name": "sniper_shrapnel",
+          "passive": false,
+          "ultimate": false,
+          "charges": 1,
+          "max_charges": 3,
+          "charge_restore_time": 24
+        }"#;
+        let ability: Ability =
+            serde_json::from_str(json_str).expect("Failed to deserialize Ability");
+
+        assert!(!ability.is_on_cooldown());
+        assert_eq!(ability.charges_ready(), Some(1));
+        assert_eq!(ability.max_charges(), Some(3));
+        assert_eq!(
+            ability.charge_restore_duration(),
+            Some(Duration::from_secs(24))
+        );
+    }
+
+    #[test]
+    fn test_game_abilities_accessors() {
+        let json_str = r#"{
+    "ability3": {
+      "name": "skeleton_king_reincarnation",
+      "level": 0,
+      "can_cast": false,
+      "passive": true,
+      "ability_active": true,
+      "cooldown": 0,
+      "ultimate": true
+    },
+    "ability0": {
+      "name": "skeleton_king_hellfire_blast",
+      "level": 0,
+      "can_cast": false,
+      "passive": false,
+      "ability_active": true,
+      "cooldown": 0,
+      "ultimate": false
+    },
+    "ability2": {
+      "name": "skeleton_king_mortal_strike",
+      "level": 0,
+      "can_cast": false,
+      "passive": true,
+      "ability_active": true,
+      "cooldown": 0,
+      "ultimate": false
+    },
+    "ability1": {
+      "name": "skeleton_king_vampiric_aura",
+      "level": 0,
+      "can_cast": false,
+      "passive": false,
+      "ability_active": true,
+      "cooldown": 0,
+      "ultimate": false
+    }
+}"#;
+        let abilities: GameAbilities =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameAbilities");
+
+        assert!(matches!(abilities, GameAbilities::Playing(_)));
+        assert_eq!(
+            abilities.ultimate().map(|a| a.name()),
+            Some("skeleton_king_reincarnation")
+        );
+        assert_eq!(
+            abilities.get(1).map(|a| a.name()),
+            Some("skeleton_king_vampiric_aura")
+        );
+        assert!(abilities.get(5).is_none());
+        assert_eq!(
+            abilities
+                .abilities_in_order()
+                .iter()
+                .map(|a| a.name())
+                .collect::<Vec<&str>>(),
+            vec![
+                "skeleton_king_hellfire_blast",
+                "skeleton_king_vampiric_aura",
+                "skeleton_king_mortal_strike",
+                "skeleton_king_reincarnation",
+            ]
+        );
+        assert_eq!(
+            abilities
+                .abilities_in_order()
+                .iter()
+                .map(|a| a.display_name())
+                .collect::<Vec<String>>(),
+            vec![
+                "Hellfire Blast",
+                "Vampiric Aura",
+                "Mortal Strike",
+                "Reincarnation"
+            ]
+        );
         assert!(abilities
+            .abilities_in_order()
             .iter()
-            .any(|a| a.name == "plus_guild_banner".to_owned()));
-        assert!(abilities
-            .iter()
-            .any(|a| a.name == "marci_unleash".to_owned()));
+            .all(|a| !a.is_dota_plus()));
+    }
+
+    #[test]
+    fn test_game_abilities_deserialize_spectating() {
+        let ability = r#"{
+          "name": "axe_berserkers_call",
+          "level": 1,
+          "can_cast": true,
+          "passive": false,
+          "ability_active": true,
+          "cooldown": 0,
+          "ultimate": false
+        }"#;
+        let json_str = format!(
+            r#"{{
+              "team2": {{"player0": {{"ability0": {ability}}}}},
+              "team3": {{"player5": [{ability}, {ability}]}}
+            }}"#
+        );
+        let abilities: GameAbilities =
+            serde_json::from_str(&json_str).expect("Failed to deserialize GameAbilities");
+
+        let GameAbilities::Spectating(teams) = abilities else {
+            panic!("Expected spectating abilities");
+        };
+        assert!(teams[&Team::Radiant][&PlayerID::from(0)].contains_key(&AbilityID(0)));
+
+        let dire = &teams[&Team::Dire][&PlayerID::from(5)];
+        assert_eq!(dire.len(), 2);
+        assert_eq!(dire[&AbilityID(1)].name(), "axe_berserkers_call");
+    }
+
+    #[test]
+    fn test_ability_id_deserialize_invalid() {
+        for key in ["abilityfoo", "ability_", "hero"] {
+            let result = serde_json::from_str::<AbilityID>(&format!("\"{}\"", key));
+            let error = result.expect_err("Invalid ability ID deserialized");
+
+            assert!(error.to_string().contains(key));
+        }
+
+        let id: AbilityID =
+            serde_json::from_str("\"ability3\"").expect("Failed to deserialize AbilityID");
+        assert_eq!(id, AbilityID(3));
     }
 }
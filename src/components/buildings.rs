@@ -9,25 +9,139 @@ pub enum BuildingsError {
     EmptyBuilding,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BuildingInformation {
     health: u32,
     max_health: u32,
 }
 
+impl BuildingInformation {
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Current health as a fraction of max health, between 0 and 1.
+    pub fn health_fraction(&self) -> f32 {
+        if self.max_health == 0 {
+            return 0.0;
+        }
+
+        self.health as f32 / self.max_health as f32
+    }
+}
+
+/// Barracks spawn either melee or ranged creeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaxType {
+    Melee,
+    Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BuildingClass {
-    Rax,
+    Rax(RaxType),
     Ancient,
-    Tower,
+    /// A tower and its tier, from 1 to 4.
+    Tower(u8),
+}
+
+/// The lane a building is in. Buildings in the base, like the ancient, are in no lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Top,
+    Mid,
+    Bot,
+    Base,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Lane {
+    fn from_name(s: &str) -> Option<Lane> {
+        match s {
+            "top" => Some(Lane::Top),
+            "mid" => Some(Lane::Mid),
+            "bot" => Some(Lane::Bot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Buildings {
     #[serde(flatten)]
     inner: HashMap<String, BuildingInformation>,
 }
 
 impl Buildings {
+    /// Classify a building by its name, like `dota_goodguys_tower1_mid`, `bad_rax_melee_bot`,
+    /// or `dota_badguys_fort`. Returns None for names that are not recognized.
+    pub fn classify(name: &str) -> Option<(BuildingClass, Lane)> {
+        let parts: Vec<&str> = name.split('_').collect();
+
+        match parts.as_slice() {
+            ["dota", "goodguys" | "badguys", "fort"] => Some((BuildingClass::Ancient, Lane::Base)),
+            ["good" | "bad", "rax", rax_type, lane] => {
+                let rax_type = match *rax_type {
+                    "melee" => RaxType::Melee,
+                    "range" => RaxType::Range,
+                    _ => return None,
+                };
+                Some((BuildingClass::Rax(rax_type), Lane::from_name(lane)?))
+            }
+            ["dota", "goodguys" | "badguys", tower, lane] => {
+                let tier = tower.strip_prefix("tower")?.parse::<u8>().ok()?;
+                if !(1..=4).contains(&tier) {
+                    return None;
+                }
+                Some((BuildingClass::Tower(tier), Lane::from_name(lane)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn iter_class<'a>(
+        &'a self,
+        predicate: impl Fn(&BuildingClass) -> bool + 'a,
+    ) -> impl Iterator<Item = (&'a str, &'a BuildingInformation)> + 'a {
+        self.inner
+            .iter()
+            .filter_map(move |(name, info)| match Buildings::classify(name) {
+                Some((class, _)) if predicate(&class) => Some((name.as_str(), info)),
+                _ => None,
+            })
+    }
+
+    pub fn towers(&self) -> impl Iterator<Item = (&str, &BuildingInformation)> {
+        self.iter_class(|c| matches!(c, BuildingClass::Tower(_)))
+    }
+
+    pub fn racks(&self) -> impl Iterator<Item = (&str, &BuildingInformation)> {
+        self.iter_class(|c| matches!(c, BuildingClass::Rax(_)))
+    }
+
+    pub fn ancient(&self) -> impl Iterator<Item = (&str, &BuildingInformation)> {
+        self.iter_class(|c| matches!(c, BuildingClass::Ancient))
+    }
+
+    /// Names of all buildings with no health left.
+    pub fn destroyed(&self) -> impl Iterator<Item = &str> {
+        self.inner
+            .iter()
+            .filter(|(_, info)| info.is_destroyed())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The highest tier among the towers still standing in a lane.
+    pub fn tier_standing(&self, lane: Lane) -> Option<u8> {
+        self.inner
+            .iter()
+            .filter(|(_, info)| !info.is_destroyed())
+            .filter_map(|(name, _)| match Buildings::classify(name) {
+                Some((BuildingClass::Tower(tier), l)) if l == lane => Some(tier),
+                _ => None,
+            })
+            .max()
+    }
+
     pub fn get_building_information(&self, name: &str) -> Option<&BuildingInformation> {
         match self.inner.get(name) {
             Some(i) => Some(i),
@@ -124,5 +238,91 @@ mod tests {
             serde_json::from_str(json_str).expect("Failed to deserialize Buildings");
 
         assert!(buildings.contains_building("dota_badguys_tower3_mid"));
+        assert_eq!(buildings.towers().count(), 9);
+        assert_eq!(buildings.racks().count(), 6);
+        assert_eq!(buildings.ancient().count(), 1);
+
+        assert_eq!(buildings.destroyed().count(), 0);
+        assert_eq!(buildings.tier_standing(Lane::Mid), Some(3));
+        assert_eq!(buildings.tier_standing(Lane::Top), Some(4));
+        assert_eq!(buildings.tier_standing(Lane::Base), None);
+
+        let tower = buildings
+            .get_building_information("dota_badguys_tower1_bot")
+            .unwrap();
+        assert!(!tower.is_destroyed());
+        assert_eq!(tower.health_fraction(), 1752.0 / 1800.0);
+    }
+
+    #[test]
+    fn test_buildings_destroyed() {
+        let json_str = r#"{
+    "dota_goodguys_tower1_mid": {
+      "health": 0,
+      "max_health": 1800
+    },
+    "dota_goodguys_tower2_mid": {
+      "health": 900,
+      "max_health": 1800
+    },
+    "dota_goodguys_tower3_mid": {
+      "health": 0,
+      "max_health": 2500
+    },
+    "dota_goodguys_tower1_top": {
+      "health": 0,
+      "max_health": 1800
+    }
+  }"#;
+        let buildings: Buildings =
+            serde_json::from_str(json_str).expect("Failed to deserialize Buildings");
+
+        let mut destroyed: Vec<&str> = buildings.destroyed().collect();
+        destroyed.sort();
+
+        assert_eq!(
+            destroyed,
+            vec![
+                "dota_goodguys_tower1_mid",
+                "dota_goodguys_tower1_top",
+                "dota_goodguys_tower3_mid"
+            ]
+        );
+        assert_eq!(buildings.tier_standing(Lane::Mid), Some(2));
+        assert_eq!(buildings.tier_standing(Lane::Top), None);
+
+        let tower = buildings
+            .get_building_information("dota_goodguys_tower2_mid")
+            .unwrap();
+        assert_eq!(tower.health_fraction(), 0.5);
+    }
+
+    #[test]
+    fn test_buildings_classify() {
+        assert_eq!(
+            Buildings::classify("dota_goodguys_tower1_mid"),
+            Some((BuildingClass::Tower(1), Lane::Mid))
+        );
+        assert_eq!(
+            Buildings::classify("dota_badguys_tower4_top"),
+            Some((BuildingClass::Tower(4), Lane::Top))
+        );
+        assert_eq!(
+            Buildings::classify("bad_rax_melee_bot"),
+            Some((BuildingClass::Rax(RaxType::Melee), Lane::Bot))
+        );
+        assert_eq!(
+            Buildings::classify("good_rax_range_top"),
+            Some((BuildingClass::Rax(RaxType::Range), Lane::Top))
+        );
+        assert_eq!(
+            Buildings::classify("dota_badguys_fort"),
+            Some((BuildingClass::Ancient, Lane::Base))
+        );
+        assert_eq!(Buildings::classify("dota_goodguys_tower5_mid"), None);
+        assert_eq!(Buildings::classify("bad_rax_siege_mid"), None);
+        assert_eq!(Buildings::classify("dota_goodguys_tower1_left"), None);
+        assert_eq!(Buildings::classify("npc_dota_roshan"), None);
+        assert_eq!(Buildings::classify(""), None);
     }
 }
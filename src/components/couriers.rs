@@ -0,0 +1,276 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, de::Error, de::IntoDeserializer, ser, Deserialize, Serialize};
+use serde_json::{map, Value};
+use thiserror;
+
+use super::wearables::deserialize_slot_by_string_split;
+use super::PlayerID;
+
+#[derive(thiserror::Error, Debug)]
+pub enum CouriersError {
+    #[error("failed to parse courier ID in `{0}`")]
+    ParseIDError(String),
+    #[error("failed to parse courier item slot in `{0}`")]
+    ParseSlotError(String),
+    #[error("failed to parse courier owner from value `{0}`")]
+    ParseOwnerError(Value),
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Copy, Clone)]
+pub struct CourierID(u8);
+
+impl fmt::Display for CourierID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CourierID({})", self.0)
+    }
+}
+
+impl From<u8> for CourierID {
+    fn from(n: u8) -> CourierID {
+        CourierID(n)
+    }
+}
+
+impl FromStr for CourierID {
+    type Err = CouriersError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s
+            .parse::<u8>()
+            .map_err(|_| CouriersError::ParseIDError(s.to_owned()))?;
+        Ok(CourierID::from(id))
+    }
+}
+
+impl<'de> Deserialize<'de> for CourierID {
+    fn deserialize<D>(deserializer: D) -> Result<CourierID, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserialize_slot_by_string_split::<D, CourierID>(deserializer, vec!["courier"])
+    }
+}
+
+impl Serialize for CourierID {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(&format!("courier{}", self.0))
+    }
+}
+
+/// An item carried by a courier.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CourierItem {
+    #[serde(skip_deserializing)]
+    pub index: u8,
+    pub name: String,
+    pub owner: Option<i16>,
+}
+
+impl CourierItem {
+    pub fn is_empty(&self) -> bool {
+        self.name == "empty"
+    }
+}
+
+/// Parse the items carried by a courier, keyed by `itemN`, sorted by their slot.
+fn deserialize_courier_items<'de, D>(deserializer: D) -> Result<Vec<CourierItem>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let m = map::Map::<String, Value>::deserialize(deserializer)?;
+    let mut items: Vec<CourierItem> = Vec::with_capacity(m.len());
+
+    for (key, value) in m {
+        let index = key
+            .strip_prefix("item")
+            .and_then(|n| n.parse::<u8>().ok())
+            .ok_or_else(|| D::Error::custom(CouriersError::ParseSlotError(key.clone())))?;
+        let mut item: CourierItem = serde_json::from_value(value).map_err(D::Error::custom)?;
+        item.index = index;
+        items.push(item);
+    }
+
+    items.sort_by_key(|i| i.index);
+
+    Ok(items)
+}
+
+/// Couriers are owned by the player ID sent as a number, unlike the `playerN` keys used elsewhere.
+fn deserialize_owner<'de, D>(deserializer: D) -> Result<PlayerID, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+
+    match &value {
+        Value::Number(n) => match n.as_u64().and_then(|n| u8::try_from(n).ok()) {
+            Some(n) => PlayerID::new(n).map_err(D::Error::custom),
+            None => Err(D::Error::custom(CouriersError::ParseOwnerError(value))),
+        },
+        Value::String(s) => PlayerID::deserialize(s.to_owned().into_deserializer()),
+        _ => Err(D::Error::custom(CouriersError::ParseOwnerError(value))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Courier {
+    #[serde(skip_deserializing)]
+    pub id: u8,
+    pub health: u32,
+    pub max_health: u32,
+    pub alive: bool,
+    #[serde(default, deserialize_with = "deserialize_courier_items")]
+    pub items: Vec<CourierItem>,
+    #[serde(deserialize_with = "deserialize_owner")]
+    pub owner: PlayerID,
+}
+
+impl Courier {
+    /// Iterate over the items carried by this courier, skipping empty slots.
+    pub fn carried_items(&self) -> impl Iterator<Item = &CourierItem> {
+        self.items.iter().filter(|i| !i.is_empty())
+    }
+}
+
+impl fmt::Display for Courier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.alive {
+            write!(
+                f,
+                "Courier {}: {}/{}",
+                self.id, self.health, self.max_health
+            )
+        } else {
+            write!(f, "Courier {}: Dead", self.id)
+        }
+    }
+}
+
+/// Wrapper for couriers sent when spectating, sorted by their ID.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Couriers {
+    couriers: Vec<Courier>,
+}
+
+impl Couriers {
+    pub fn len(&self) -> usize {
+        self.couriers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.couriers.is_empty()
+    }
+
+    pub fn couriers(&self) -> &[Courier] {
+        &self.couriers
+    }
+
+    pub fn get(&self, id: &CourierID) -> Option<&Courier> {
+        self.couriers.iter().find(|c| c.id == id.0)
+    }
+
+    /// Get the courier owned by the given player.
+    pub fn owned_by(&self, owner: &PlayerID) -> Option<&Courier> {
+        self.couriers.iter().find(|c| &c.owner == owner)
+    }
+}
+
+impl<'de> Deserialize<'de> for Couriers {
+    fn deserialize<D>(deserializer: D) -> Result<Couriers, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let m = map::Map::<String, Value>::deserialize(deserializer)?;
+        let mut couriers: Vec<Courier> = Vec::with_capacity(m.len());
+
+        for (key, value) in m {
+            let id: CourierID = CourierID::deserialize(key.into_deserializer())?;
+            let mut courier: Courier = serde_json::from_value(value).map_err(D::Error::custom)?;
+            courier.id = id.0;
+            couriers.push(courier);
+        }
+
+        couriers.sort_by_key(|c| c.id);
+
+        Ok(Couriers { couriers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_couriers_deserialize() {
+        let json_str = r#"{
+  "courier1": {
+    "health": 0,
+    "max_health": 75,
+    "alive": false,
+    "boost": false,
+    "flying_upgrade": false,
+    "shield": false,
+    "respawn_time_remaining": 42,
+    "xpos": 7000,
+    "ypos": 6500,
+    "yaw": 0,
+    "items": {},
+    "owner": 5
+  },
+  "courier0": {
+    "health": 75,
+    "max_health": 75,
+    "alive": true,
+    "boost": false,
+    "flying_upgrade": true,
+    "shield": false,
+    "respawn_time_remaining": 0,
+    "xpos": -6700,
+    "ypos": -6200,
+    "yaw": 45,
+    "items": {
+      "item1": {
+        "name": "empty"
+      },
+      "item0": {
+        "name": "item_tango",
+        "owner": 0
+      }
+    },
+    "owner": 0
+  }
+}"#;
+
+        let couriers: Couriers =
+            serde_json::from_str(json_str).expect("Failed to deserialize Couriers");
+
+        assert_eq!(couriers.len(), 2);
+        assert_eq!(
+            couriers
+                .couriers()
+                .iter()
+                .map(|c| c.id)
+                .collect::<Vec<u8>>(),
+            vec![0, 1]
+        );
+
+        let first = couriers.get(&CourierID::from(0)).unwrap();
+        assert!(first.alive);
+        assert_eq!(first.health, 75);
+        assert_eq!(first.owner, PlayerID::from(0));
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.items[0].index, 0);
+        assert_eq!(first.items[0].name, "item_tango");
+        assert_eq!(first.carried_items().count(), 1);
+
+        let second = couriers.owned_by(&PlayerID::from(5)).unwrap();
+        assert_eq!(second.id, 1);
+        assert!(!second.alive);
+        assert!(second.items.is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+
+use serde::{de, ser, Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use super::{roshan_respawn_window, GameState, Team};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoshanKilled {
+    pub game_time: u32,
+    pub killed_by_team: Team,
+    pub killer_player_id: i16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AegisPickedUp {
+    pub game_time: u32,
+    pub player_id: i16,
+    pub snatched: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AegisDenied {
+    pub game_time: u32,
+    pub player_id: i16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CourierKilled {
+    pub game_time: u32,
+    pub courier_team: Team,
+    pub killer_player_id: i16,
+    pub owner_player_id: i16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BountyRunePickup {
+    pub game_time: u32,
+    pub player_id: i16,
+    pub team: Team,
+    pub bounty_value: u32,
+    pub team_gold: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tip {
+    pub game_time: u32,
+    pub sender_player_id: i16,
+    pub receiver_player_id: i16,
+    pub tip_amount: u32,
+}
+
+/// An event sent by Dota in the top-level events array, identified by its `event_type`.
+/// Events of unknown type, or that fail to parse, are kept as raw JSON in `Undefined`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    RoshanKilled(RoshanKilled),
+    AegisPickedUp(AegisPickedUp),
+    AegisDenied(AegisDenied),
+    CourierKilled(CourierKilled),
+    BountyRunePickup(BountyRunePickup),
+    Tip(Tip),
+    Undefined(Value),
+}
+
+impl Event {
+    /// The `event_type` string used by Dota to identify this event.
+    pub fn event_type(&self) -> Option<&str> {
+        match self {
+            Event::RoshanKilled(_) => Some("roshan_killed"),
+            Event::AegisPickedUp(_) => Some("aegis_picked_up"),
+            Event::AegisDenied(_) => Some("aegis_denied"),
+            Event::CourierKilled(_) => Some("courier_killed"),
+            Event::BountyRunePickup(_) => Some("bounty_rune_pickup"),
+            Event::Tip(_) => Some("tip"),
+            Event::Undefined(v) => v.get("event_type").and_then(|t| t.as_str()),
+        }
+    }
+
+    pub fn game_time(&self) -> Option<u32> {
+        match self {
+            Event::RoshanKilled(e) => Some(e.game_time),
+            Event::AegisPickedUp(e) => Some(e.game_time),
+            Event::AegisDenied(e) => Some(e.game_time),
+            Event::CourierKilled(e) => Some(e.game_time),
+            Event::BountyRunePickup(e) => Some(e.game_time),
+            Event::Tip(e) => Some(e.game_time),
+            Event::Undefined(v) => v
+                .get("game_time")
+                .and_then(|t| t.as_u64())
+                .map(|t| t as u32),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::RoshanKilled(e) => write!(f, "Roshan killed by {}", e.killed_by_team),
+            Event::AegisPickedUp(e) => write!(f, "Aegis picked up by player {}", e.player_id),
+            Event::AegisDenied(e) => write!(f, "Aegis denied by player {}", e.player_id),
+            Event::CourierKilled(e) => write!(f, "{} courier killed", e.courier_team),
+            Event::BountyRunePickup(e) => write!(
+                f,
+                "Bounty rune picked up by player {}: {} gold",
+                e.player_id, e.bounty_value
+            ),
+            Event::Tip(e) => write!(
+                f,
+                "Player {} tipped player {}",
+                e.sender_player_id, e.receiver_player_id
+            ),
+            Event::Undefined(v) => write!(f, "Undefined: {}", v),
+        }
+    }
+}
+
+/// Keep track of the last time Roshan was killed, observing the events of each GameState.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RoshanTracker {
+    killed_at: Option<u32>,
+}
+
+impl RoshanTracker {
+    pub fn new() -> Self {
+        RoshanTracker::default()
+    }
+
+    /// Record the game time of the latest Roshan kill sent in the events of gs.
+    pub fn observe(&mut self, gs: &GameState) {
+        let killed_at = gs
+            .events()
+            .iter()
+            .filter_map(|e| match e {
+                Event::RoshanKilled(k) => Some(k.game_time),
+                _ => None,
+            })
+            .max();
+
+        if let Some(killed_at) = killed_at {
+            if self.killed_at.is_none_or(|previous| killed_at > previous) {
+                self.killed_at = Some(killed_at);
+            }
+        }
+    }
+
+    /// The game time Roshan was last killed at, if he was killed at all.
+    pub fn killed_at(&self) -> Option<u32> {
+        self.killed_at
+    }
+
+    /// Seconds from current_game_time until the earliest and latest time Roshan can respawn.
+    /// Returns None when Roshan hasn't been killed, or has surely respawned already.
+    pub fn time_remaining(&self, current_game_time: u32) -> Option<(u32, u32)> {
+        let (earliest, latest) = roshan_respawn_window(self.killed_at?);
+
+        if current_game_time >= latest {
+            return None;
+        }
+
+        Some((
+            earliest.saturating_sub(current_game_time),
+            latest - current_game_time,
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Event, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+
+        let event = match value.get("event_type").and_then(|t| t.as_str()) {
+            Some("roshan_killed") => serde_json::from_value(value.clone()).map(Event::RoshanKilled),
+            Some("aegis_picked_up") => {
+                serde_json::from_value(value.clone()).map(Event::AegisPickedUp)
+            }
+            Some("aegis_denied") => serde_json::from_value(value.clone()).map(Event::AegisDenied),
+            Some("courier_killed") => {
+                serde_json::from_value(value.clone()).map(Event::CourierKilled)
+            }
+            Some("bounty_rune_pickup") => {
+                serde_json::from_value(value.clone()).map(Event::BountyRunePickup)
+            }
+            Some("tip") => serde_json::from_value(value.clone()).map(Event::Tip),
+            _ => return Ok(Event::Undefined(value)),
+        };
+
+        match event {
+            Ok(e) => Ok(e),
+            Err(e) => {
+                log::debug!("failed to parse event, keeping it as undefined: {}", e);
+                Ok(Event::Undefined(value))
+            }
+        }
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        #[derive(Serialize)]
+        struct Tagged<'a, T> {
+            event_type: &'a str,
+            #[serde(flatten)]
+            event: &'a T,
+        }
+
+        let event_type = self.event_type().unwrap_or_default();
+
+        match self {
+            Event::RoshanKilled(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::AegisPickedUp(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::AegisDenied(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::CourierKilled(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::BountyRunePickup(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::Tip(event) => Tagged { event_type, event }.serialize(serializer),
+            Event::Undefined(v) => v.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_deserialize() {
+        let json_str = r#"[
+  {
+    "game_time": 1312,
+    "event_type": "roshan_killed",
+    "killed_by_team": "radiant",
+    "killer_player_id": 3
+  },
+  {
+    "game_time": 1315,
+    "event_type": "aegis_picked_up",
+    "player_id": 3,
+    "snatched": false
+  },
+  {
+    "game_time": 1402,
+    "event_type": "courier_killed",
+    "courier_team": "dire",
+    "killer_player_id": 1,
+    "owner_player_id": 7
+  },
+  {
+    "game_time": 1410,
+    "event_type": "some_new_event",
+    "value": 42
+  },
+  {
+    "game_time": 1420,
+    "event_type": "tip",
+    "sender_player_id": "not a number"
+  }
+]"#;
+
+        let events: Vec<Event> =
+            serde_json::from_str(json_str).expect("Failed to deserialize Events");
+
+        assert_eq!(events.len(), 5);
+        assert!(matches!(
+            &events[0],
+            Event::RoshanKilled(RoshanKilled {
+                killed_by_team: Team::Radiant,
+                killer_player_id: 3,
+                ..
+            })
+        ));
+        assert!(matches!(&events[1], Event::AegisPickedUp(_)));
+        assert!(matches!(&events[2], Event::CourierKilled(_)));
+        assert!(matches!(&events[3], Event::Undefined(_)));
+        assert_eq!(events[3].event_type(), Some("some_new_event"));
+        assert_eq!(events[3].game_time(), Some(1410));
+        assert!(matches!(&events[4], Event::Undefined(_)));
+    }
+
+    #[test]
+    fn test_roshan_tracker() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1688514013
+            },
+            "events": [
+                {
+                    "game_time": 1312,
+                    "event_type": "roshan_killed",
+                    "killed_by_team": "radiant",
+                    "killer_player_id": 3
+                }
+            ]
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        let mut tracker = RoshanTracker::new();
+        assert_eq!(tracker.time_remaining(1400), None);
+
+        tracker.observe(&gs);
+        assert_eq!(tracker.killed_at(), Some(1312));
+        assert_eq!(tracker.time_remaining(1312), Some((480, 660)));
+        assert_eq!(tracker.time_remaining(1852), Some((0, 120)));
+        assert_eq!(tracker.time_remaining(1972), None);
+    }
+
+    #[test]
+    fn test_event_serialize_includes_event_type() {
+        let json_str = r#"{"event_type":"aegis_denied","game_time":900,"player_id":8}"#;
+
+        let event: Event = serde_json::from_str(json_str).expect("Failed to deserialize Event");
+        let value = serde_json::to_value(&event).expect("Failed to serialize Event");
+
+        assert!(matches!(event, Event::AegisDenied(_)));
+        assert_eq!(value["event_type"], "aegis_denied");
+        assert_eq!(value["player_id"], 8);
+    }
+}
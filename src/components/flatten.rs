@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::abilities::GameAbilities;
+use super::heroes::GameHeroes;
+use super::items::GameItems;
+use super::players::GamePlayers;
+use super::wearables::GameWearables;
+use super::GameState;
+
+impl GameState {
+    /// Flatten every numeric field of this event into a map keyed by dotted paths, as
+    /// ingested by time-series databases like InfluxDB or Graphite.
+    ///
+    /// Keys follow the blocks sent by Dota, like `hero.health`, `player.gold` or
+    /// `map.game_time`, with nested objects joined by dots, like
+    /// `buildings.radiant.dota_goodguys_tower1_mid.health`, and arrays by index. Booleans
+    /// become 0 or 1, while strings and missing fields are skipped.
+    ///
+    /// When spectating, the team level of the per-player blocks is dropped, as player IDs
+    /// are unique across teams. The player block of player N is keyed by `playerN`, like
+    /// `player5.net_worth`, and their other blocks are nested under it, like
+    /// `player5.hero.health` or `player5.abilities.ability0.level`.
+    pub fn flatten(&self) -> BTreeMap<String, f64> {
+        let mut flattened = BTreeMap::new();
+
+        let blocks = match serde_json::to_value(self) {
+            Ok(Value::Object(blocks)) => blocks,
+            _ => return flattened,
+        };
+
+        for (block, value) in blocks {
+            let block = match block.as_str() {
+                "players" => "player",
+                "heroes" => "hero",
+                block => block,
+            };
+
+            if !self.is_spectated_block(block) {
+                flatten_value(block.to_owned(), value, &mut flattened);
+                continue;
+            }
+
+            for players in object_values(value) {
+                let Value::Object(players) = players else {
+                    continue;
+                };
+
+                for (player, value) in players {
+                    let prefix = match block {
+                        "player" => player,
+                        block => format!("{}.{}", player, block),
+                    };
+                    flatten_value(prefix, value, &mut flattened);
+                }
+            }
+        }
+
+        flattened
+    }
+
+    /// Whether a per-player block was sent keyed by team and player, as when spectating.
+    fn is_spectated_block(&self, block: &str) -> bool {
+        match block {
+            "player" => matches!(self.players, Some(GamePlayers::Spectating(_))),
+            "hero" => matches!(self.heroes, Some(GameHeroes::Spectating(_))),
+            "abilities" => matches!(self.abilities, Some(GameAbilities::Spectating(_))),
+            "items" => matches!(self.items, Some(GameItems::Spectating(_))),
+            "wearables" => matches!(self.wearables, Some(GameWearables::Spectating(_))),
+            _ => false,
+        }
+    }
+}
+
+fn object_values(value: Value) -> impl Iterator<Item = Value> {
+    match value {
+        Value::Object(m) => Some(m.into_iter().map(|(_, v)| v)),
+        _ => None,
+    }
+    .into_iter()
+    .flatten()
+}
+
+fn flatten_value(key: String, value: Value, flattened: &mut BTreeMap<String, f64>) {
+    match value {
+        Value::Number(n) => {
+            if let Some(n) = n.as_f64() {
+                flattened.insert(key, n);
+            }
+        }
+        Value::Bool(b) => {
+            flattened.insert(key, if b { 1.0 } else { 0.0 });
+        }
+        Value::Object(m) => {
+            for (k, v) in m {
+                flatten_value(format!("{}.{}", key, k), v, flattened);
+            }
+        }
+        Value::Array(values) => {
+            for (i, v) in values.into_iter().enumerate() {
+                flatten_value(format!("{}.{}", key, i), v, flattened);
+            }
+        }
+        Value::String(_) | Value::Null => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_spectating() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1688514013
+            },
+            "player": {
+                "team3": {
+                    "player5": {
+                        "activity": "playing",
+                        "assists": 3,
+                        "commands_issued": 1200,
+                        "deaths": 1,
+                        "denies": 4,
+                        "gold": 850,
+                        "gold_from_creep_kills": 0,
+                        "gold_from_hero_kills": 0,
+                        "gold_from_income": 0,
+                        "gold_from_shared": 0,
+                        "gold_reliable": 300,
+                        "gold_unreliable": 550,
+                        "gpm": 410,
+                        "kill_list": {},
+                        "kill_streak": 0,
+                        "kills": 2,
+                        "last_hits": 60,
+                        "name": "Third",
+                        "net_worth": 7350,
+                        "steamid": "76561198069076692",
+                        "team_name": "dire",
+                        "xpm": 520
+                    }
+                }
+            },
+            "hero": {
+                "team3": {
+                    "player5": {"id": 2, "name": "npc_dota_hero_axe", "health": 700, "alive": true}
+                }
+            },
+            "abilities": {
+                "team3": {
+                    "player5": {
+                        "ability0": {
+                            "name": "axe_berserkers_call",
+                            "level": 2,
+                            "can_cast": true,
+                            "passive": false,
+                            "ability_active": true,
+                            "cooldown": 0,
+                            "ultimate": false
+                        }
+                    }
+                }
+            }
+        }"#;
+
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+        let flattened = gs.flatten();
+
+        assert_eq!(flattened.get("provider.timestamp"), Some(&1688514013.0));
+        assert_eq!(flattened.get("player5.net_worth"), Some(&7350.0));
+        assert_eq!(flattened.get("player5.kills"), Some(&2.0));
+        assert_eq!(flattened.get("player5.hero.health"), Some(&700.0));
+        assert_eq!(flattened.get("player5.hero.alive"), Some(&1.0));
+        assert_eq!(
+            flattened.get("player5.abilities.ability0.level"),
+            Some(&2.0)
+        );
+        assert!(!flattened.contains_key("player5.hero.name"));
+        assert!(flattened.keys().all(|k| !k.contains("dire")));
+    }
+}
@@ -1,11 +1,41 @@
 use std::collections::HashMap;
 use std::fmt;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "tracing")]
+use tracing as log;
 
-use super::{PlayerID, Team};
+use super::{title_case, PlayerID, Position, Team};
 
-#[derive(Serialize, Deserialize, Debug)]
+const HERO_NAME_PREFIX: &str = "npc_dota_hero_";
+
+/// Heroes whose display name differs from their internal name.
+#[cfg(feature = "hero-names")]
+const HERO_DISPLAY_NAMES: &[(&str, &str)] = &[
+    ("abyssal_underlord", "Underlord"),
+    ("antimage", "Anti-Mage"),
+    ("centaur", "Centaur Warrunner"),
+    ("doom_bringer", "Doom"),
+    ("furion", "Nature's Prophet"),
+    ("keeper_of_the_light", "Keeper of the Light"),
+    ("life_stealer", "Lifestealer"),
+    ("magnataur", "Magnus"),
+    ("necrolyte", "Necrophos"),
+    ("nevermore", "Shadow Fiend"),
+    ("obsidian_destroyer", "Outworld Destroyer"),
+    ("queenofpain", "Queen of Pain"),
+    ("rattletrap", "Clockwerk"),
+    ("shredder", "Timbersaw"),
+    ("skeleton_king", "Wraith King"),
+    ("treant", "Treant Protector"),
+    ("vengefulspirit", "Vengeful Spirit"),
+    ("windrunner", "Windranger"),
+    ("wisp", "Io"),
+    ("zuus", "Zeus"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Hero {
     pub xpos: Option<i32>,
     pub ypos: Option<i32>,
@@ -42,6 +72,97 @@ pub struct Hero {
     pub talent_6: Option<bool>,
     pub talent_7: Option<bool>,
     pub talent_8: Option<bool>,
+    pub attributes_level: Option<u8>,
+    pub selected_unit: Option<bool>,
+    /// Other units controlled by the player, like Lone Druid's Spirit Bear, Meepo's clones or
+    /// Arc Warden's Tempest Double. None for heroes with a single unit.
+    #[serde(
+        rename = "hero_units",
+        default,
+        deserialize_with = "deserialize_hero_units"
+    )]
+    pub additional_units: Option<Vec<Hero>>,
+}
+
+/// Parse the additional units of a hero, sent either as an array or as an object keyed by
+/// `unitN`, in which case they are sorted by N. No units are parsed as None.
+///
+/// Units that fail to parse are skipped, so they never fail parsing the hero itself.
+fn deserialize_hero_units<'de, D>(deserializer: D) -> Result<Option<Vec<Hero>>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let parse = |value: Value| match serde_json::from_value::<Hero>(value) {
+        Ok(unit) => Some(unit),
+        Err(e) => {
+            log::debug!("failed to parse hero unit, skipping it: {}", e);
+            None
+        }
+    };
+
+    let units: Vec<Hero> = match Value::deserialize(deserializer)? {
+        Value::Array(units) => units.into_iter().filter_map(parse).collect(),
+        Value::Object(units) => {
+            let mut keyed: Vec<(u32, Hero)> = units
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let index = key
+                        .trim_start_matches(|c: char| !c.is_ascii_digit())
+                        .parse::<u32>();
+                    match index {
+                        Ok(index) => parse(value).map(|unit| (index, unit)),
+                        Err(_) => {
+                            log::debug!("invalid hero unit key `{}`, skipping it", key);
+                            None
+                        }
+                    }
+                })
+                .collect();
+
+            keyed.sort_by_key(|(index, _)| *index);
+            keyed.into_iter().map(|(_, unit)| unit).collect()
+        }
+        _ => Vec::new(),
+    };
+
+    if units.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(units))
+    }
+}
+
+impl Hero {
+    /// The hero's position in the map, if both coordinates were sent.
+    pub fn position(&self) -> Option<Position> {
+        match (self.xpos, self.ypos) {
+            (Some(x), Some(y)) => Some(Position::new(x, y)),
+            _ => None,
+        }
+    }
+
+    /// A readable name, like `Crystal Maiden` for `npc_dota_hero_crystal_maiden`.
+    ///
+    /// By default, the name is title-cased from the internal name, which is wrong for heroes
+    /// renamed since release, like `Skeleton King`. Enable the `hero-names` feature to use the
+    /// current name of those heroes instead, like `Wraith King`.
+    pub fn display_name(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        let name = name.strip_prefix(HERO_NAME_PREFIX).unwrap_or(name);
+
+        #[cfg(feature = "hero-names")]
+        if let Some((_, display_name)) = HERO_DISPLAY_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(display_name.to_string());
+        }
+
+        Some(title_case(name))
+    }
+
+    /// Iterate over every unit controlled by the player: this hero, followed by its
+    /// additional units, if any.
+    pub fn units(&self) -> impl Iterator<Item = &Hero> {
+        std::iter::once(self).chain(self.additional_units.iter().flatten())
+    }
 }
 
 impl fmt::Display for Hero {
@@ -57,7 +178,7 @@ impl fmt::Display for Hero {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum GameHeroes {
     Spectating(HashMap<Team, HashMap<PlayerID, Hero>>),
@@ -78,6 +199,30 @@ mod tests {
 
         assert_eq!(hero.id, -1);
         assert_eq!(hero.name, None);
+        assert_eq!(hero.selected_unit, None);
+        assert_eq!(hero.position(), None);
+        assert_eq!(hero.display_name(), None);
+    }
+
+    #[test]
+    fn test_hero_display_name() {
+        let hero: Hero =
+            serde_json::from_str(r#"{"id": 5, "name": "npc_dota_hero_crystal_maiden"}"#)
+                .expect("Failed to deserialize Hero");
+
+        assert_eq!(hero.display_name().as_deref(), Some("Crystal Maiden"));
+    }
+
+    #[test]
+    fn test_renamed_hero_display_name() {
+        let hero: Hero =
+            serde_json::from_str(r#"{"id": 42, "name": "npc_dota_hero_skeleton_king"}"#)
+                .expect("Failed to deserialize Hero");
+
+        #[cfg(feature = "hero-names")]
+        assert_eq!(hero.display_name().as_deref(), Some("Wraith King"));
+        #[cfg(not(feature = "hero-names"))]
+        assert_eq!(hero.display_name().as_deref(), Some("Skeleton King"));
     }
 
     #[test]
@@ -86,6 +231,7 @@ mod tests {
         "aghanims_scepter": false,
         "aghanims_shard": false,
         "alive": true,
+        "attributes_level": 1,
         "break": false,
         "buyback_cooldown": 0,
         "buyback_cost": 379,
@@ -125,5 +271,83 @@ mod tests {
 
         assert_eq!(hero.name, Some(String::from("npc_dota_hero_marci")));
         assert_eq!(hero.max_health, Some(1100));
+        assert_eq!(hero.selected_unit, Some(true));
+        assert_eq!(hero.attributes_level, Some(1));
+        assert_eq!(hero.additional_units, None);
+        assert_eq!(hero.units().count(), 1);
+    }
+
+    #[test]
+    fn test_hero_additional_units() {
+        let json_str = r#"{
+        "id": 80,
+        "name": "npc_dota_hero_lone_druid",
+        "alive": true,
+        "health": 620,
+        "max_health": 620,
+        "xpos": -1200,
+        "ypos": 800,
+        "hero_units": {
+          "unit1": {
+            "id": 80,
+            "name": "npc_dota_lone_druid_bear",
+            "alive": true,
+            "health": 1100,
+            "max_health": 1100,
+            "xpos": -1100,
+            "ypos": 900
+          }
+        }
+      }"#;
+
+        let hero: Hero = serde_json::from_str(json_str).expect("Failed to deserialize Hero");
+
+        assert_eq!(hero.name.as_deref(), Some("npc_dota_hero_lone_druid"));
+        assert_eq!(hero.health, Some(620));
+
+        let units = hero.additional_units.as_ref().expect("missing units");
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].name.as_deref(), Some("npc_dota_lone_druid_bear"));
+        assert_eq!(units[0].health, Some(1100));
+        assert_eq!(units[0].position(), Some(Position::new(-1100, 900)));
+
+        let names: Vec<&str> = hero.units().filter_map(|u| u.name.as_deref()).collect();
+        assert_eq!(
+            names,
+            vec!["npc_dota_hero_lone_druid", "npc_dota_lone_druid_bear"]
+        );
+
+        let round_trip: Hero =
+            serde_json::from_value(serde_json::to_value(&hero).unwrap()).unwrap();
+        assert_eq!(round_trip, hero);
+    }
+
+    #[test]
+    fn test_hero_additional_units_array() {
+        let hero: Hero = serde_json::from_str(
+            r#"{"id": 82, "name": "npc_dota_hero_meepo", "hero_units": [
+                {"id": 82, "name": "npc_dota_hero_meepo", "health": 300},
+                {"id": 82, "name": "npc_dota_hero_meepo", "health": 200}
+            ]}"#,
+        )
+        .expect("Failed to deserialize Hero");
+
+        let health: Vec<Option<u16>> = hero.units().map(|u| u.health).collect();
+        assert_eq!(health, vec![None, Some(300), Some(200)]);
+
+        let hero: Hero = serde_json::from_str(r#"{"id": 82, "hero_units": {}}"#)
+            .expect("Failed to deserialize Hero");
+        assert_eq!(hero.additional_units, None);
+        // Units that don't parse never fail the hero.
+        let hero: Hero = serde_json::from_str(
+            r#"{"id": 82, "hero_units": {"unit1": {"id": "meepo"}, "other": {"id": 82}, "unit2": {"id": 82, "health": 100}}}"#,
+        )
+        .expect("Failed to deserialize Hero");
+        let health: Vec<Option<u16>> = hero.units().map(|u| u.health).collect();
+        assert_eq!(health, vec![None, Some(100)]);
+
+        let hero: Hero = serde_json::from_str(r#"{"id": 82, "hero_units": "unexpected"}"#)
+            .expect("Failed to deserialize Hero");
+        assert_eq!(hero.additional_units, None);
     }
 }
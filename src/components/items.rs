@@ -2,8 +2,9 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::num::ParseIntError;
+use std::time::Duration;
 
-use serde::{de, Deserialize, Serialize};
+use serde::{de, ser, ser::SerializeMap, Deserialize, Serialize};
 use thiserror::Error;
 
 use super::{PlayerID, Team};
@@ -20,7 +21,7 @@ pub enum ItemsError {
     UnknownItemContainer(String),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(from = "String")]
 pub enum Rune {
     Arcane,
@@ -37,7 +38,7 @@ pub enum Rune {
 
 impl From<String> for Rune {
     fn from(s: String) -> Self {
-        return match s.as_str() {
+        match s.as_str() {
             "arcane" => Rune::Arcane,
             "bounty" => Rune::Bounty,
             "double_damage" => Rune::DoubleDamage,
@@ -48,7 +49,34 @@ impl From<String> for Rune {
             "regen" => Rune::Regeneration,
             "shield" => Rune::Shield,
             _ => Rune::Undefined(s),
-        };
+        }
+    }
+}
+
+impl Rune {
+    /// The name Dota uses for this rune.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Rune::Arcane => "arcane",
+            Rune::Bounty => "bounty",
+            Rune::DoubleDamage => "double_damage",
+            Rune::Empty => "empty",
+            Rune::Haste => "haste",
+            Rune::Illusion => "illusion",
+            Rune::Invisibility => "invisibility",
+            Rune::Regeneration => "regen",
+            Rune::Shield => "shield",
+            Rune::Undefined(s) => s,
+        }
+    }
+}
+
+impl Serialize for Rune {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -69,19 +97,23 @@ impl fmt::Display for Rune {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 #[serde(try_from = "String")]
 pub enum ItemContainer {
     Inventory(u8),
     Stash(u8),
     Teleport,
     Neutral,
+    /// A slot keeping a neutral item from a previous tier, like `preserved_neutral6`.
+    PreservedNeutral(u8),
 }
 
 impl ItemContainer {
     fn index(&self) -> u8 {
         match self {
-            ItemContainer::Inventory(n) | ItemContainer::Stash(n) => *n,
+            ItemContainer::Inventory(n)
+            | ItemContainer::Stash(n)
+            | ItemContainer::PreservedNeutral(n) => *n,
             ItemContainer::Teleport | ItemContainer::Neutral => 0,
         }
     }
@@ -94,6 +126,7 @@ impl fmt::Display for ItemContainer {
             ItemContainer::Stash(n) => write!(f, "Stash: {}", n),
             ItemContainer::Teleport => write!(f, "Teleport"),
             ItemContainer::Neutral => write!(f, "Neutral"),
+            ItemContainer::PreservedNeutral(n) => write!(f, "Preserved Neutral: {}", n),
         }
     }
 }
@@ -117,6 +150,7 @@ impl TryFrom<String> for ItemContainer {
             "stash" => Ok(ItemContainer::Stash(numeric_slot)),
             "teleport" => Ok(ItemContainer::Teleport),
             "neutral" => Ok(ItemContainer::Neutral),
+            "preserved_neutral" => Ok(ItemContainer::PreservedNeutral(numeric_slot)),
             s => Err(ItemsError::UnknownItemContainer(s.to_owned())),
         }
     }
@@ -132,23 +166,68 @@ fn find_first_numeric(s: &str) -> Option<usize> {
     None
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Item {
     name: String,
     purchaser: i16,
+    #[serde(skip_serializing_if = "Option::is_none")]
     contains_rune: Option<Rune>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     can_cast: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     cooldown: Option<u16>,
     passive: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     charges: Option<u16>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+impl Item {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The player that bought the item, which may differ from the player carrying it.
+    /// Returns None when the purchaser is not a player slot, like the `-1` sent by Dota.
+    pub fn purchaser_id(&self) -> Option<PlayerID> {
+        u8::try_from(self.purchaser)
+            .ok()
+            .and_then(|n| PlayerID::new(n).ok())
+    }
+
+    /// The remaining cooldown, if the item has one, like a teleport scroll.
+    pub fn cooldown_duration(&self) -> Option<Duration> {
+        self.cooldown.map(|cd| Duration::from_secs(cd as u64))
+    }
+
+    /// Whether the item can currently be cast. Passive items are never ready.
+    pub fn is_ready(&self) -> bool {
+        self.can_cast == Some(true)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum ItemSlot {
     Empty { index: u8 },
     Full { index: u8, item: Item },
 }
 
+impl ItemSlot {
+    /// The index of this slot within its container.
+    pub fn index(&self) -> u8 {
+        match self {
+            ItemSlot::Empty { index } | ItemSlot::Full { index, item: _ } => *index,
+        }
+    }
+
+    /// The item in this slot, if it's not empty.
+    pub fn item(&self) -> Option<&Item> {
+        match self {
+            ItemSlot::Empty { index: _ } => None,
+            ItemSlot::Full { index: _, item } => Some(item),
+        }
+    }
+}
+
 impl fmt::Display for ItemSlot {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -158,22 +237,84 @@ impl fmt::Display for ItemSlot {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum GameItems {
     Playing(Items),
     Spectating(HashMap<Team, HashMap<PlayerID, Items>>),
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Items {
     inventory: Vec<ItemSlot>,
     stash: Vec<ItemSlot>,
     teleport: ItemSlot,
     neutral: ItemSlot,
+    preserved_neutrals: Vec<ItemSlot>,
 }
 
 impl Items {
+    /// Get the inventory slot with the given index. Slots 0 to 5 are the main inventory,
+    /// while 6 to 8 are the backpack.
+    pub fn inventory_slot(&self, i: u8) -> Option<&ItemSlot> {
+        self.inventory
+            .binary_search_by_key(&i, |slot| slot.index())
+            .ok()
+            .map(|position| &self.inventory[position])
+    }
+
+    /// Iterate over the slots of every container: inventory, stash, teleport, neutral, and
+    /// preserved neutrals.
+    pub fn slots(&self) -> impl Iterator<Item = &ItemSlot> {
+        self.inventory
+            .iter()
+            .chain(self.stash.iter())
+            .chain(std::iter::once(&self.teleport))
+            .chain(std::iter::once(&self.neutral))
+            .chain(self.preserved_neutrals.iter())
+    }
+
+    /// Iterate over the inventory slots holding an item, skipping empty slots.
+    pub fn filled_inventory(&self) -> impl Iterator<Item = &ItemSlot> {
+        self.inventory
+            .iter()
+            .filter(|slot| matches!(slot, ItemSlot::Full { .. }))
+    }
+
+    /// Find the first item with the given name in any container.
+    pub fn find(&self, name: &str) -> Option<&Item> {
+        self.slots()
+            .filter_map(|slot| slot.item())
+            .find(|item| item.name == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+
+    /// Whether any item with the given name can be cast right now.
+    pub fn has_item_ready(&self, name: &str) -> bool {
+        self.slots()
+            .filter_map(|slot| slot.item())
+            .any(|item| item.name == name && item.is_ready())
+    }
+
+    /// Get the stash slot with the given index.
+    pub fn stash_slot(&self, i: u8) -> Option<&ItemSlot> {
+        self.stash
+            .binary_search_by_key(&i, |slot| slot.index())
+            .ok()
+            .map(|position| &self.stash[position])
+    }
+
+    /// Get the preserved neutral slot with the given index.
+    pub fn preserved_neutral_slot(&self, i: u8) -> Option<&ItemSlot> {
+        self.preserved_neutrals
+            .binary_search_by_key(&i, |slot| slot.index())
+            .ok()
+            .map(|position| &self.preserved_neutrals[position])
+    }
+
     pub fn is_inventory_empty(&self) -> bool {
         self.inventory.iter().all(|item| match item {
             ItemSlot::Empty { index: _ } => true,
@@ -245,13 +386,69 @@ impl fmt::Display for Items {
             writeln!(f, "Neutral: {}", self.neutral)?;
         }
 
+        for slot in self.preserved_neutrals.iter() {
+            writeln!(f, "Preserved Neutral: {}", slot)?;
+        }
+
         Ok(())
     }
 }
 
+/// Serialize an ItemSlot the way Dota sends it: empty slots only contain an "empty" name.
+struct NativeItemSlot<'a>(&'a ItemSlot);
+
+impl Serialize for NativeItemSlot<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self.0 {
+            ItemSlot::Empty { index: _ } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("name", "empty")?;
+                map.end()
+            }
+            ItemSlot::Full { index: _, item } => item.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Items {
+    /// Serialize Items with the same container keys sent by Dota, so they can be deserialized again.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(
+            self.inventory.len() + self.stash.len() + self.preserved_neutrals.len() + 2,
+        ))?;
+
+        for slot in self.inventory.iter() {
+            map.serialize_entry(&format!("slot{}", slot.index()), &NativeItemSlot(slot))?;
+        }
+
+        for slot in self.stash.iter() {
+            map.serialize_entry(&format!("stash{}", slot.index()), &NativeItemSlot(slot))?;
+        }
+
+        map.serialize_entry("teleport0", &NativeItemSlot(&self.teleport))?;
+        map.serialize_entry("neutral0", &NativeItemSlot(&self.neutral))?;
+
+        for slot in self.preserved_neutrals.iter() {
+            map.serialize_entry(
+                &format!("preserved_neutral{}", slot.index()),
+                &NativeItemSlot(slot),
+            )?;
+        }
+
+        map.end()
+    }
+}
+
 impl<'de> Deserialize<'de> for Items {
     /// Deserialize Items by flattening JSON of ItemContainers.
-    /// Items can be contained in Inventory, Stash, Teleport slot, or Neutral slot.
+    /// Items can be contained in Inventory, Stash, Teleport slot, Neutral slot, or Preserved
+    /// Neutral slots.
     fn deserialize<D>(deserializer: D) -> Result<Items, D::Error>
     where
         D: de::Deserializer<'de>,
@@ -276,6 +473,7 @@ impl<'de> Deserialize<'de> for Items {
         let helper = Helper::deserialize(deserializer)?;
         let mut inventory: Vec<ItemSlot> = Vec::new();
         let mut stash: Vec<ItemSlot> = Vec::new();
+        let mut preserved_neutrals: Vec<ItemSlot> = Vec::new();
         let mut neutral: ItemSlot = ItemSlot::Empty { index: 0 };
         let mut teleport: ItemSlot = ItemSlot::Empty { index: 0 };
 
@@ -320,14 +518,21 @@ impl<'de> Deserialize<'de> for Items {
                 ItemContainer::Neutral => {
                     neutral = item;
                 }
+                ItemContainer::PreservedNeutral(_) => preserved_neutrals.push(item),
             }
         }
 
+        // Slots are read in map order, sort them so they can be displayed in order.
+        inventory.sort_by_key(|slot| slot.index());
+        stash.sort_by_key(|slot| slot.index());
+        preserved_neutrals.sort_by_key(|slot| slot.index());
+
         Ok(Items {
             inventory,
             stash,
             teleport,
             neutral,
+            preserved_neutrals,
         })
     }
 }
@@ -403,9 +608,100 @@ mod tests {
             items.teleport,
             ItemSlot::Full { index: 0, item: _ }
         ));
+        assert_eq!(
+            items.teleport.item().unwrap().cooldown_duration(),
+            Some(Duration::from_secs(96))
+        );
 
         assert!(items.is_inventory_empty());
+        assert_eq!(items.filled_inventory().count(), 0);
         assert!(items.is_stash_empty());
         assert!(items.is_neutral_empty());
+
+        for (i, slot) in items.inventory.iter().enumerate() {
+            assert_eq!(slot.index() as usize, i);
+        }
+        for (i, slot) in items.stash.iter().enumerate() {
+            assert_eq!(slot.index() as usize, i);
+        }
+        assert!(matches!(
+            items.inventory_slot(8),
+            Some(ItemSlot::Empty { index: 8 })
+        ));
+        assert!(items.inventory_slot(9).is_none());
+        assert!(matches!(
+            items.stash_slot(5),
+            Some(ItemSlot::Empty { index: 5 })
+        ));
+
+        let original: serde_json::Value = serde_json::from_str(json_str).unwrap();
+        assert_eq!(serde_json::to_value(&items).unwrap(), original);
+    }
+
+    #[test]
+    fn test_item_purchaser_id() {
+        let bought: Item =
+            serde_json::from_str(r#"{"name": "item_tango", "purchaser": 0, "passive": false}"#)
+                .expect("Failed to deserialize Item");
+        let unowned: Item =
+            serde_json::from_str(r#"{"name": "item_tango", "purchaser": -1, "passive": false}"#)
+                .expect("Failed to deserialize Item");
+
+        assert_eq!(bought.purchaser_id(), Some(PlayerID::from(0)));
+        assert_eq!(unowned.purchaser_id(), None);
+    }
+
+    #[test]
+    fn test_items_serialize_round_trip() {
+        let json_str = r#"{
+          "slot0": {
+              "name": "item_bottle",
+              "purchaser": 2,
+              "contains_rune": "double_damage",
+              "can_cast": true,
+              "cooldown": 0,
+              "passive": false,
+              "charges": 3
+          },
+          "slot1": {
+              "name": "empty"
+          },
+          "stash0": {
+              "name": "item_ward_observer",
+              "purchaser": 2,
+              "passive": false,
+              "charges": 1
+          },
+          "teleport0": {
+              "name": "empty"
+          },
+          "neutral0": {
+              "name": "item_arcane_ring",
+              "purchaser": 2,
+              "can_cast": false,
+              "cooldown": 12,
+              "passive": false
+          },
+          "preserved_neutral6": {
+              "name": "item_trusty_shovel",
+              "purchaser": 2,
+              "can_cast": true,
+              "cooldown": 0,
+              "passive": false
+          },
+          "preserved_neutral7": {
+              "name": "empty"
+          }
+        }"#;
+
+        let items: Items = serde_json::from_str(json_str).expect("Failed to deserialize items");
+        let serialized = serde_json::to_string(&items).expect("Failed to serialize items");
+        let deserialized: Items =
+            serde_json::from_str(&serialized).expect("Failed to deserialize serialized items");
+
+        assert_eq!(deserialized, items);
+        assert!(serialized.contains(r#""contains_rune":"double_damage""#));
+        assert!(deserialized.contains("item_trusty_shovel"));
+        assert!(serialized.contains(r#""preserved_neutral7":{"name":"empty"}"#));
     }
 }
@@ -0,0 +1,324 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, de::Error, de::IntoDeserializer, ser, Deserialize, Serialize};
+use serde_json::{map, Value};
+use thiserror;
+
+use super::wearables::deserialize_slot_by_string_split;
+use super::{Position, Team};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MinimapError {
+    #[error("failed to parse minimap object ID in `{0}`")]
+    ParseIDError(String),
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Copy, Clone)]
+pub struct MinimapObjectID(u32);
+
+impl fmt::Display for MinimapObjectID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MinimapObjectID({})", self.0)
+    }
+}
+
+impl From<u32> for MinimapObjectID {
+    fn from(n: u32) -> MinimapObjectID {
+        MinimapObjectID(n)
+    }
+}
+
+impl FromStr for MinimapObjectID {
+    type Err = MinimapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s
+            .parse::<u32>()
+            .map_err(|_| MinimapError::ParseIDError(s.to_owned()))?;
+        Ok(MinimapObjectID::from(id))
+    }
+}
+
+impl<'de> Deserialize<'de> for MinimapObjectID {
+    fn deserialize<D>(deserializer: D) -> Result<MinimapObjectID, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserialize_slot_by_string_split::<D, MinimapObjectID>(deserializer, vec!["o"])
+    }
+}
+
+impl Serialize for MinimapObjectID {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(&format!("o{}", self.0))
+    }
+}
+
+/// The two kinds of wards that can be placed on the map
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum WardType {
+    Observer,
+    Sentry,
+}
+
+impl fmt::Display for WardType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WardType::Observer => write!(f, "Observer"),
+            WardType::Sentry => write!(f, "Sentry"),
+        }
+    }
+}
+
+/// What a minimap object represents, classified by its unit name
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "String")]
+pub enum MinimapObjectKind {
+    Hero,
+    Creep,
+    Tower,
+    Barracks,
+    Ancient,
+    Courier,
+    Ward(WardType),
+    Undefined(String),
+}
+
+impl From<String> for MinimapObjectKind {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "npc_dota_observer_wards" => MinimapObjectKind::Ward(WardType::Observer),
+            "npc_dota_sentry_wards" => MinimapObjectKind::Ward(WardType::Sentry),
+            n if n.starts_with("npc_dota_hero_") => MinimapObjectKind::Hero,
+            n if n.starts_with("npc_dota_courier") => MinimapObjectKind::Courier,
+            n if n.starts_with("npc_dota_creep") || n.starts_with("npc_dota_neutral") => {
+                MinimapObjectKind::Creep
+            }
+            n if n.contains("_tower") => MinimapObjectKind::Tower,
+            n if n.contains("_rax") => MinimapObjectKind::Barracks,
+            n if n.ends_with("_fort") => MinimapObjectKind::Ancient,
+            _ => MinimapObjectKind::Undefined(s),
+        }
+    }
+}
+
+impl fmt::Display for MinimapObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MinimapObjectKind::Hero => write!(f, "Hero"),
+            MinimapObjectKind::Creep => write!(f, "Creep"),
+            MinimapObjectKind::Tower => write!(f, "Tower"),
+            MinimapObjectKind::Barracks => write!(f, "Barracks"),
+            MinimapObjectKind::Ancient => write!(f, "Ancient"),
+            MinimapObjectKind::Courier => write!(f, "Courier"),
+            MinimapObjectKind::Ward(w) => write!(f, "{} Ward", w),
+            MinimapObjectKind::Undefined(s) => write!(f, "Undefined: {}", s),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MinimapObject {
+    #[serde(skip_deserializing)]
+    pub id: u32,
+    pub xpos: i32,
+    pub ypos: i32,
+    pub image: Option<String>,
+    pub team: u8,
+    pub name: Option<String>,
+    pub unitname: Option<String>,
+    pub yaw: Option<i32>,
+    pub visionrange: Option<u32>,
+}
+
+impl MinimapObject {
+    /// The team owning this object. Dota sends the team as its number, e.g. 2 for Radiant.
+    pub fn team(&self) -> Team {
+        Team::from(format!("team{}", self.team))
+    }
+
+    /// Classify this object by its unit name, falling back to its name.
+    pub fn kind(&self) -> MinimapObjectKind {
+        match self.unitname.as_ref().or(self.name.as_ref()) {
+            Some(n) => MinimapObjectKind::from(n.to_owned()),
+            None => MinimapObjectKind::Undefined("".to_owned()),
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.xpos, self.ypos)
+    }
+
+    pub fn is_ward(&self) -> bool {
+        matches!(self.kind(), MinimapObjectKind::Ward(_))
+    }
+}
+
+impl fmt::Display for MinimapObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at ({}, {})", self.kind(), self.xpos, self.ypos)
+    }
+}
+
+/// Wrapper for objects displayed in the minimap, sorted by their ID.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Minimap {
+    objects: Vec<MinimapObject>,
+}
+
+impl Minimap {
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn objects(&self) -> &[MinimapObject] {
+        &self.objects
+    }
+
+    pub fn get(&self, id: &MinimapObjectID) -> Option<&MinimapObject> {
+        self.objects.iter().find(|o| o.id == id.0)
+    }
+
+    /// Iterate over all observer and sentry wards in the minimap.
+    pub fn wards(&self) -> impl Iterator<Item = &MinimapObject> {
+        self.objects.iter().filter(|o| o.is_ward())
+    }
+}
+
+impl<'de> Deserialize<'de> for Minimap {
+    fn deserialize<D>(deserializer: D) -> Result<Minimap, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let m = map::Map::<String, Value>::deserialize(deserializer)?;
+        let mut objects: Vec<MinimapObject> = Vec::with_capacity(m.len());
+
+        for (key, value) in m {
+            let id: MinimapObjectID = MinimapObjectID::deserialize(key.into_deserializer())?;
+            let mut object: MinimapObject =
+                serde_json::from_value(value).map_err(D::Error::custom)?;
+            object.id = id.0;
+            objects.push(object);
+        }
+
+        objects.sort_by_key(|o| o.id);
+
+        Ok(Minimap { objects })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_deserialize() {
+        let json_str = r#"{
+  "o0": {
+    "xpos": -5696,
+    "ypos": -5312,
+    "image": "minimap_ancient",
+    "team": 2,
+    "yaw": 0,
+    "unitname": "npc_dota_goodguys_fort",
+    "visionrange": 900
+  },
+  "o1": {
+    "xpos": -1544,
+    "ypos": -1408,
+    "image": "minimap_tower90",
+    "team": 2,
+    "yaw": 0,
+    "unitname": "npc_dota_goodguys_tower1_mid",
+    "visionrange": 1900
+  },
+  "o12": {
+    "xpos": -1664,
+    "ypos": -1216,
+    "image": "minimap_herocircle",
+    "team": 2,
+    "yaw": 135,
+    "unitname": "npc_dota_hero_skeleton_king",
+    "name": "npc_dota_hero_skeleton_king",
+    "visionrange": 1800
+  },
+  "o3": {
+    "xpos": 512,
+    "ypos": 256,
+    "image": "minimap_ward_obs",
+    "team": 3,
+    "unitname": "npc_dota_observer_wards",
+    "visionrange": 1600
+  },
+  "o4": {
+    "xpos": 768,
+    "ypos": 128,
+    "image": "minimap_ward_sent",
+    "team": 3,
+    "unitname": "npc_dota_sentry_wards",
+    "visionrange": 150
+  },
+  "o5": {
+    "xpos": 4000,
+    "ypos": 3500,
+    "image": "minimap_creep",
+    "team": 3,
+    "unitname": "npc_dota_creep_badguys_melee",
+    "visionrange": 750
+  }
+}"#;
+
+        let minimap: Minimap =
+            serde_json::from_str(json_str).expect("Failed to deserialize Minimap");
+
+        assert_eq!(minimap.len(), 6);
+        assert_eq!(
+            minimap.objects().iter().map(|o| o.id).collect::<Vec<u32>>(),
+            vec![0, 1, 3, 4, 5, 12]
+        );
+
+        let ancient = minimap.get(&MinimapObjectID::from(0)).unwrap();
+        assert_eq!(ancient.kind(), MinimapObjectKind::Ancient);
+        assert_eq!(ancient.team(), Team::Radiant);
+
+        let tower = minimap.get(&MinimapObjectID::from(1)).unwrap();
+        assert_eq!(tower.kind(), MinimapObjectKind::Tower);
+
+        let hero = minimap.get(&MinimapObjectID::from(12)).unwrap();
+        assert_eq!(hero.kind(), MinimapObjectKind::Hero);
+        assert_eq!(hero.yaw, Some(135));
+
+        let creep = minimap.get(&MinimapObjectID::from(5)).unwrap();
+        assert_eq!(creep.kind(), MinimapObjectKind::Creep);
+        assert_eq!(creep.team(), Team::Dire);
+
+        let wards: Vec<&MinimapObject> = minimap.wards().collect();
+        assert_eq!(wards.len(), 2);
+        assert_eq!(wards[0].kind(), MinimapObjectKind::Ward(WardType::Observer));
+        assert_eq!(wards[1].kind(), MinimapObjectKind::Ward(WardType::Sentry));
+    }
+
+    #[test]
+    fn test_minimap_object_kind_from_str() {
+        assert_eq!(
+            MinimapObjectKind::from("npc_dota_goodguys_melee_rax_bot".to_string()),
+            MinimapObjectKind::Barracks
+        );
+        assert_eq!(
+            MinimapObjectKind::from("npc_dota_courier".to_string()),
+            MinimapObjectKind::Courier
+        );
+        assert!(matches!(
+            MinimapObjectKind::from("npc_dota_roshan".to_string()),
+            MinimapObjectKind::Undefined(_)
+        ));
+    }
+}
@@ -1,35 +1,59 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 
 use serde::{de, de::Error, Deserialize, Serialize};
-use serde_json::{map, Value};
+use serde_json::Value;
+
+use crate::config::{GsiData, MissingComponent};
 
 pub mod abilities;
 pub mod buildings;
+pub mod couriers;
+pub mod events;
+mod flatten;
 pub mod heroes;
 pub mod items;
+pub mod minimap;
 pub mod players;
+pub mod scoreboard;
+#[cfg(feature = "csv")]
+pub mod stat_rows;
 pub mod team;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod wearables;
 
-use abilities::GameAbilities;
+use abilities::{Ability, AbilityID, GameAbilities};
 use buildings::Buildings;
+use couriers::Couriers;
+use events::Event;
 use heroes::{GameHeroes, Hero};
 use items::{GameItems, Items};
-use players::{GamePlayers, PlayerID};
+use minimap::Minimap;
+use players::{GamePlayers, PlayerActivity, PlayerID};
+use scoreboard::Scoreboard;
 use team::Team;
-use wearables::GameWearables;
+use wearables::{GameWearables, Wearables};
 
 /// Represents Game State Integration authentication via an optional token
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Auth {
     token: Option<String>,
 }
 
+impl Auth {
+    /// The token set in the `auth` block of the Game State configuration file, if any.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
 /// An enum of all possible GAMERULES states
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(from = "String")]
 pub enum DotaGameRulesState {
+    #[default]
     Disconnected,
     InProgress,
     HeroSelection,
@@ -41,12 +65,14 @@ pub enum DotaGameRulesState {
     WaitingForMap,
     WaitingForPlayers,
     CustomGameSetup,
+    ScenarioSetup,
+    TeamShowcase,
     Undefined(String),
 }
 
 impl From<String> for DotaGameRulesState {
     fn from(s: String) -> Self {
-        return match s.as_str() {
+        match s.as_str() {
             "DOTA_GAMERULES_STATE_DISCONNECT" => DotaGameRulesState::Disconnected,
             "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS" => DotaGameRulesState::InProgress,
             "DOTA_GAMERULES_STATE_HERO_SELECTION" => DotaGameRulesState::HeroSelection,
@@ -60,8 +86,22 @@ impl From<String> for DotaGameRulesState {
                 DotaGameRulesState::WaitingForPlayers
             }
             "DOTA_GAMERULES_STATE_CUSTOM_GAME_SETUP" => DotaGameRulesState::CustomGameSetup,
+            "DOTA_GAMERULES_STATE_SCENARIO_SETUP" => DotaGameRulesState::ScenarioSetup,
+            "DOTA_GAMERULES_STATE_TEAM_SHOWCASE" => DotaGameRulesState::TeamShowcase,
             _ => DotaGameRulesState::Undefined(s),
-        };
+        }
+    }
+}
+
+impl DotaGameRulesState {
+    /// Whether the game has started: heroes are in the map after strategy time.
+    pub fn is_in_game(&self) -> bool {
+        matches!(
+            self,
+            DotaGameRulesState::StrategyTime
+                | DotaGameRulesState::PreGame
+                | DotaGameRulesState::InProgress
+        )
     }
 }
 
@@ -79,13 +119,23 @@ impl fmt::Display for DotaGameRulesState {
             DotaGameRulesState::WaitingForMap => write!(f, "Waiting For Map"),
             DotaGameRulesState::WaitingForPlayers => write!(f, "Waiting For Players"),
             DotaGameRulesState::CustomGameSetup => write!(f, "Custom Game Setup"),
+            DotaGameRulesState::ScenarioSetup => write!(f, "Scenario Setup"),
+            DotaGameRulesState::TeamShowcase => write!(f, "Team Showcase"),
             DotaGameRulesState::Undefined(s) => write!(f, "Undefined: {}", s),
         }
     }
 }
 
+/// The Steam app ID of Dota 2, sent by Dota in the provider block.
+pub const DOTA_APP_ID: u32 = 570;
+
+/// The name sent by Dota in the provider block.
+pub const DOTA_PROVIDER_NAME: &str = "Dota 2";
+
 /// The Game State Integration provider, will be Dota
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// The default provider is the one sent by Dota, with a version and timestamp of 0.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Provider {
     name: String,
     #[serde(alias = "appid")]
@@ -94,14 +144,112 @@ pub struct Provider {
     timestamp: u32,
 }
 
+impl Default for Provider {
+    fn default() -> Self {
+        Provider {
+            name: DOTA_PROVIDER_NAME.to_owned(),
+            app_id: DOTA_APP_ID,
+            version: 0,
+            timestamp: 0,
+        }
+    }
+}
+
+impl Provider {
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_app_id(mut self, app_id: u32) -> Self {
+        self.app_id = app_id;
+        self
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u32) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn app_id(&self) -> u32 {
+        self.app_id
+    }
+
+    /// Whether the event was sent by Dota 2, rather than by another game's integration.
+    pub fn is_dota(&self) -> bool {
+        self.name == DOTA_PROVIDER_NAME && self.app_id == DOTA_APP_ID
+    }
+
+    /// Unix timestamp, in seconds, of when Dota sent the event.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+
+    /// When Dota sent the event, in UTC.
+    #[cfg(feature = "chrono")]
+    pub fn datetime(&self) -> chrono::DateTime<chrono::Utc> {
+        let sent = std::time::UNIX_EPOCH + Duration::from_secs(self.timestamp as u64);
+        chrono::DateTime::from(sent)
+    }
+}
+
 impl fmt::Display for Provider {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} {}", self.name, self.version)
     }
 }
 
+/// Day and night alternate every 5 minutes of game clock.
+const DAY_NIGHT_CYCLE_SECONDS: i32 = 300;
+
+/// Bounty runes spawn at the horn and every 3 minutes of game clock afterwards.
+const BOUNTY_RUNE_INTERVAL_SECONDS: i32 = 180;
+
+/// Power runes first spawn at 6 minutes of game clock.
+const FIRST_POWER_RUNE_SECONDS: i32 = 360;
+
+/// After the first spawn, power runes spawn every 2 minutes of game clock.
+const POWER_RUNE_INTERVAL_SECONDS: i32 = 120;
+
+/// Roshan respawns at a random time between 8 and 11 minutes after being killed.
+const ROSHAN_MIN_RESPAWN_SECONDS: u32 = 480;
+const ROSHAN_MAX_RESPAWN_SECONDS: u32 = 660;
+
+/// The earliest and latest game time Roshan can respawn at after being killed at killed_at.
+fn roshan_respawn_window(killed_at: u32) -> (u32, u32) {
+    (
+        killed_at + ROSHAN_MIN_RESPAWN_SECONDS,
+        killed_at + ROSHAN_MAX_RESPAWN_SECONDS,
+    )
+}
+
+/// Whether it's currently day or night in the map
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Day,
+    Night,
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeOfDay::Day => write!(f, "Day"),
+            TimeOfDay::Night => write!(f, "Night"),
+        }
+    }
+}
+
 /// Represents a Dota Game State Integration map
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Map {
     name: String,
     #[serde(alias = "matchid")]
@@ -115,6 +263,158 @@ pub struct Map {
     win_team: Team,
     customgamename: String,
     ward_purchase_cooldown: Option<u16>,
+    #[serde(default)]
+    radiant_score: Option<u32>,
+    #[serde(default)]
+    dire_score: Option<u32>,
+}
+
+impl Map {
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_match_id(mut self, match_id: impl Into<String>) -> Self {
+        self.match_id = match_id.into();
+        self
+    }
+
+    pub fn with_game_time(mut self, game_time: u32) -> Self {
+        self.game_time = game_time;
+        self
+    }
+
+    pub fn with_clock_time(mut self, clock_time: i32) -> Self {
+        self.clock_time = clock_time;
+        self
+    }
+
+    pub fn with_daytime(mut self, daytime: bool) -> Self {
+        self.daytime = daytime;
+        self
+    }
+
+    pub fn with_nightstalker_night(mut self, nightstalker_night: bool) -> Self {
+        self.nightstalker_night = nightstalker_night;
+        self
+    }
+
+    pub fn with_game_state(mut self, game_state: DotaGameRulesState) -> Self {
+        self.game_state = game_state;
+        self
+    }
+
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub fn with_win_team(mut self, win_team: Team) -> Self {
+        self.win_team = win_team;
+        self
+    }
+
+    pub fn with_ward_purchase_cooldown(mut self, ward_purchase_cooldown: Option<u16>) -> Self {
+        self.ward_purchase_cooldown = ward_purchase_cooldown;
+        self
+    }
+
+    pub fn with_radiant_score(mut self, radiant_score: Option<u32>) -> Self {
+        self.radiant_score = radiant_score;
+        self
+    }
+
+    pub fn with_dire_score(mut self, dire_score: Option<u32>) -> Self {
+        self.dire_score = dire_score;
+        self
+    }
+
+    /// The team that won the game, if any. Only Radiant or Dire can win.
+    pub fn winner(&self) -> Option<Team> {
+        match self.win_team {
+            Team::Radiant | Team::Dire => Some(self.win_team.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether the game is over, with a winner decided.
+    pub fn is_finished(&self) -> bool {
+        self.winner().is_some() && matches!(self.game_state, DotaGameRulesState::PostGame)
+    }
+
+    /// Night Stalker's ultimate turns day into night, so it takes precedence over daytime.
+    pub fn time_of_day(&self) -> TimeOfDay {
+        if self.daytime && !self.nightstalker_night {
+            TimeOfDay::Day
+        } else {
+            TimeOfDay::Night
+        }
+    }
+
+    /// Seconds until day turns into night, or the other way around.
+    /// Returns None before the horn, while the clock is negative.
+    pub fn seconds_until_cycle_change(&self) -> Option<u32> {
+        if self.clock_time < 0 {
+            return None;
+        }
+
+        Some((DAY_NIGHT_CYCLE_SECONDS - self.clock_time % DAY_NIGHT_CYCLE_SECONDS) as u32)
+    }
+
+    /// Seconds until the next bounty runes spawn.
+    /// Returns None before the horn, while the clock is negative.
+    pub fn next_bounty_rune(&self) -> Option<u32> {
+        if self.clock_time < 0 {
+            return None;
+        }
+
+        Some((BOUNTY_RUNE_INTERVAL_SECONDS - self.clock_time % BOUNTY_RUNE_INTERVAL_SECONDS) as u32)
+    }
+
+    /// Seconds until the next power rune spawns.
+    /// Returns None before the horn, while the clock is negative.
+    pub fn next_power_rune(&self) -> Option<u32> {
+        if self.clock_time < 0 {
+            return None;
+        }
+
+        if self.clock_time < FIRST_POWER_RUNE_SECONDS {
+            return Some((FIRST_POWER_RUNE_SECONDS - self.clock_time) as u32);
+        }
+
+        Some((POWER_RUNE_INTERVAL_SECONDS - self.clock_time % POWER_RUNE_INTERVAL_SECONDS) as u32)
+    }
+
+    /// The earliest and latest game time Roshan can respawn at, when killed at the given game
+    /// time. See [`RoshanTracker`](events::RoshanTracker) to record the kill time from events.
+    pub fn roshan_respawn_window(&self, killed_at_game_time: u32) -> (u32, u32) {
+        roshan_respawn_window(killed_at_game_time)
+    }
+
+    /// Time elapsed since the map was loaded.
+    pub fn game_time_duration(&self) -> Duration {
+        Duration::from_secs(self.game_time as u64)
+    }
+
+    /// Time displayed in the game clock.
+    /// The clock is negative before the horn sounds, in which case None is returned.
+    pub fn clock_time_duration(&self) -> Option<Duration> {
+        u64::try_from(self.clock_time).ok().map(Duration::from_secs)
+    }
+
+    /// Time until wards can be purchased again, or zero when they can be purchased now.
+    /// Returns None when Dota didn't send the cooldown, like when spectating.
+    pub fn ward_purchase_cooldown_duration(&self) -> Option<Duration> {
+        self.ward_purchase_cooldown
+            .map(|seconds| Duration::from_secs(u64::from(seconds)))
+    }
+
+    /// Whether wards can't be purchased yet. False when the cooldown wasn't sent.
+    pub fn is_ward_on_cooldown(&self) -> bool {
+        self.ward_purchase_cooldown
+            .is_some_and(|seconds| seconds > 0)
+    }
 }
 
 impl fmt::Display for Map {
@@ -127,31 +427,94 @@ impl fmt::Display for Map {
     }
 }
 
+/// A position in the map, in the same coordinates used by Dota
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Self {
+        Position { x, y }
+    }
+
+    /// Euclidean distance to another position.
+    pub fn distance_to(&self, other: &Position) -> f64 {
+        let dx = (self.x - other.x) as f64;
+        let dy = (self.y - other.y) as f64;
+
+        dx.hypot(dy)
+    }
+}
+
+impl From<(i32, i32)> for Position {
+    fn from((x, y): (i32, i32)) -> Self {
+        Position { x, y }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// Whether the Game State Integration client is playing a match, spectating one, or in the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Playing,
+    Spectating,
+    Menu,
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Playing => write!(f, "Playing"),
+            GameMode::Spectating => write!(f, "Spectating"),
+            GameMode::Menu => write!(f, "In Menu"),
+        }
+    }
+}
+
+/// Turn an internal name like `crystal_maiden` into `Crystal Maiden`.
+fn title_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Blocks that Dota sends empty, like `{}`, are treated as missing.
+/// Any other value is parsed into T, so blocks sent in other forms, like an array, still parse.
 fn empty_map_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
 where
     D: de::Deserializer<'de>,
     T: de::DeserializeOwned + std::fmt::Debug,
 {
-    let opt = Option::<map::Map<String, Value>>::deserialize(de)?;
-
-    match opt {
+    match Option::<Value>::deserialize(de)? {
         None => Ok(None),
-        Some(m) => {
-            if m.is_empty() {
-                Ok(None)
-            } else {
-                let res: T = serde_json::from_value(Value::Object(m)).map_err(D::Error::custom)?;
-                Ok(Some(res))
-            }
-        }
+        Some(Value::Object(m)) if m.is_empty() => Ok(None),
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(D::Error::custom),
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct GameState {
     provider: Provider,
     #[serde(default, deserialize_with = "empty_map_as_none")]
     buildings: Option<HashMap<Team, Buildings>>,
+    #[serde(default, deserialize_with = "empty_map_as_none")]
     map: Option<Map>,
     #[serde(alias = "player", default, deserialize_with = "empty_map_as_none")]
     players: Option<GamePlayers>,
@@ -161,12 +524,99 @@ pub struct GameState {
     abilities: Option<GameAbilities>,
     #[serde(default, deserialize_with = "empty_map_as_none")]
     items: Option<GameItems>,
+    #[serde(default, deserialize_with = "empty_map_as_none")]
     draft: Option<HashMap<Team, HashMap<PlayerID, Value>>>,
     #[serde(default, deserialize_with = "empty_map_as_none")]
     wearables: Option<GameWearables>,
+    #[serde(default, deserialize_with = "empty_map_as_none")]
+    minimap: Option<Minimap>,
+    #[serde(default)]
+    events: Option<Vec<Event>>,
+    #[serde(default, deserialize_with = "empty_map_as_none")]
+    couriers: Option<Couriers>,
+    /// Top-level blocks not modeled by any other field, like blocks added in a Dota update.
+    #[cfg(feature = "capture-extra")]
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
 }
 
 impl GameState {
+    pub fn with_provider(mut self, provider: Provider) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    pub fn with_map(mut self, map: Map) -> Self {
+        self.map = Some(map);
+        self
+    }
+
+    pub fn with_players(mut self, players: GamePlayers) -> Self {
+        self.players = Some(players);
+        self
+    }
+
+    pub fn with_heroes(mut self, heroes: GameHeroes) -> Self {
+        self.heroes = Some(heroes);
+        self
+    }
+
+    pub fn with_abilities(mut self, abilities: GameAbilities) -> Self {
+        self.abilities = Some(abilities);
+        self
+    }
+
+    pub fn with_items(mut self, items: GameItems) -> Self {
+        self.items = Some(items);
+        self
+    }
+
+    pub fn with_buildings(mut self, buildings: HashMap<Team, Buildings>) -> Self {
+        self.buildings = Some(buildings);
+        self
+    }
+
+    pub fn with_wearables(mut self, wearables: GameWearables) -> Self {
+        self.wearables = Some(wearables);
+        self
+    }
+
+    pub fn with_minimap(mut self, minimap: Minimap) -> Self {
+        self.minimap = Some(minimap);
+        self
+    }
+
+    pub fn with_events(mut self, events: Vec<Event>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn with_couriers(mut self, couriers: Couriers) -> Self {
+        self.couriers = Some(couriers);
+        self
+    }
+
+    /// Derive the GameMode from the players block.
+    /// A missing players block, or a player in the menu, means we are in the menu.
+    pub fn mode(&self) -> GameMode {
+        match &self.players {
+            None => GameMode::Menu,
+            Some(GamePlayers::Spectating(_)) => GameMode::Spectating,
+            Some(GamePlayers::Playing(p)) => match p.activity {
+                PlayerActivity::Menu => GameMode::Menu,
+                _ => GameMode::Playing,
+            },
+        }
+    }
+
+    pub fn is_spectating(&self) -> bool {
+        self.mode() == GameMode::Spectating
+    }
+
+    pub fn get_provider(&self) -> &Provider {
+        &self.provider
+    }
+
     pub fn get_items(&self) -> Option<&Items> {
         if let Some(items) = &self.items {
             match items {
@@ -178,6 +628,18 @@ impl GameState {
         }
     }
 
+    /// Abilities of the hero being played. Returns None when spectating.
+    pub fn get_abilities(&self) -> Option<&HashMap<AbilityID, Ability>> {
+        if let Some(abilities) = &self.abilities {
+            match abilities {
+                GameAbilities::Playing(a) => Some(a),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
     pub fn get_heroes(&self) -> Option<&GameHeroes> {
         self.heroes.as_ref()
     }
@@ -186,6 +648,38 @@ impl GameState {
         self.heroes.as_ref()
     }
 
+    /// A top-level block that isn't modeled by GameState, as sent by Dota.
+    /// Blocks that are modeled, like `map`, are never returned, even when missing.
+    #[cfg(feature = "capture-extra")]
+    pub fn extra(&self, key: &str) -> Option<&Value> {
+        self.extra.get(key)
+    }
+
+    /// Events sent by Dota, or an empty slice when the events block is absent.
+    pub fn events(&self) -> &[Event] {
+        self.events.as_deref().unwrap_or(&[])
+    }
+
+    /// Wearables equipped by the hero being played. Returns None when spectating.
+    pub fn get_wearables(&self) -> Option<&Wearables> {
+        if let Some(wearables) = &self.wearables {
+            match wearables {
+                GameWearables::Playing(w) => Some(w),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_minimap(&self) -> Option<&Minimap> {
+        self.minimap.as_ref()
+    }
+
+    pub fn get_couriers(&self) -> Option<&Couriers> {
+        self.couriers.as_ref()
+    }
+
     pub fn get_hero(&self) -> Option<&Hero> {
         if let Some(heroes) = &self.heroes {
             match heroes {
@@ -197,6 +691,91 @@ impl GameState {
         }
     }
 
+    /// Join every player of a spectated game with their hero and items, by team.
+    /// Returns None when not spectating.
+    pub fn scoreboard(&self) -> Option<Scoreboard<'_>> {
+        match &self.players {
+            Some(GamePlayers::Spectating(players)) => Some(Scoreboard::new(self, players)),
+            _ => None,
+        }
+    }
+
+    /// Extract the fields most overlays need into a flat JSON object, instead of serializing
+    /// the whole state. Fields missing from the event are null.
+    ///
+    /// The object has the following keys:
+    /// * `clock_time`: seconds in the game clock.
+    /// * `game_state`: the game rules state, like `In Progress`.
+    /// * `radiant_score` and `dire_score`: kills of each team.
+    /// * `hero_name`, `hero_health` and `hero_max_health`: the hero being played.
+    /// * `gold`: gold of the player.
+    pub fn to_summary_json(&self) -> Value {
+        let map = self.map.as_ref();
+        let hero = self.get_hero();
+        let player = match &self.players {
+            Some(GamePlayers::Playing(p)) => Some(p),
+            _ => None,
+        };
+
+        serde_json::json!({
+            "clock_time": map.map(|m| m.clock_time),
+            "game_state": map.map(|m| m.game_state.to_string()),
+            "radiant_score": map.and_then(|m| m.radiant_score),
+            "dire_score": map.and_then(|m| m.dire_score),
+            "hero_name": hero.and_then(|h| h.name.as_deref()),
+            "hero_health": hero.and_then(|h| h.health),
+            "hero_max_health": hero.and_then(|h| h.max_health),
+            "gold": player.map(|p| p.gold),
+        })
+    }
+
+    /// Cross-check the components requested in expected against the components in this event,
+    /// returning the ones that are enabled but missing.
+    ///
+    /// Components that are only sent at times are skipped when they are not due: events are only
+    /// sent when something happened, couriers only to spectators, and the draft only during hero
+    /// selection and strategy time, or when the map is missing and the phase is unknown.
+    ///
+    /// Other components are legitimately missing at times too, like the hero and items while in
+    /// the menu, so a missing component is only a hint of a misconfigured data block when it's
+    /// missing across all events.
+    pub fn validate(&self, expected: &GsiData) -> Vec<MissingComponent> {
+        let is_drafting = self.map.as_ref().is_none_or(|m| {
+            matches!(
+                m.game_state,
+                DotaGameRulesState::HeroSelection | DotaGameRulesState::StrategyTime
+            )
+        });
+        let is_due = |component: &MissingComponent| match component {
+            MissingComponent::Events => false,
+            MissingComponent::Couriers => self.is_spectating(),
+            MissingComponent::Draft => is_drafting,
+            _ => true,
+        };
+
+        let components = [
+            (MissingComponent::Buildings, self.buildings.is_some()),
+            (MissingComponent::Map, self.map.is_some()),
+            (MissingComponent::Player, self.players.is_some()),
+            (MissingComponent::Hero, self.heroes.is_some()),
+            (MissingComponent::Abilities, self.abilities.is_some()),
+            (MissingComponent::Items, self.items.is_some()),
+            (MissingComponent::Draft, self.draft.is_some()),
+            (MissingComponent::Wearables, self.wearables.is_some()),
+            (MissingComponent::Events, self.events.is_some()),
+            (MissingComponent::Minimap, self.minimap.is_some()),
+            (MissingComponent::Couriers, self.couriers.is_some()),
+        ];
+
+        components
+            .into_iter()
+            .filter(|(component, present)| {
+                !present && expected.is_enabled(component.name()) && is_due(component)
+            })
+            .map(|(component, _)| component)
+            .collect()
+    }
+
     pub fn get_team_player_items(&self, team: &Team, id: &PlayerID) -> Option<&Items> {
         if let Some(items) = &self.items {
             match items {
@@ -211,6 +790,38 @@ impl GameState {
         }
     }
 
+    pub fn get_team_player_abilities(
+        &self,
+        team: &Team,
+        id: &PlayerID,
+    ) -> Option<&HashMap<AbilityID, Ability>> {
+        if let Some(abilities) = &self.abilities {
+            match abilities {
+                GameAbilities::Spectating(m) => match m.get(team) {
+                    Some(t) => t.get(id),
+                    None => None,
+                },
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_team_player_wearables(&self, team: &Team, id: &PlayerID) -> Option<&Wearables> {
+        if let Some(wearables) = &self.wearables {
+            match wearables {
+                GameWearables::Spectating(m) => match m.get(team) {
+                    Some(t) => t.get(id),
+                    None => None,
+                },
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
     pub fn get_team_player_hero(&self, team: &Team, id: &PlayerID) -> Option<&Hero> {
         if let Some(heroes) = &self.heroes {
             match heroes {
@@ -295,9 +906,314 @@ mod tests {
         assert!(gs.players.is_none());
         assert!(gs.map.is_none());
         assert!(gs.heroes.is_none());
+        assert!(gs.events().is_empty());
+        assert_eq!(gs.mode(), GameMode::Menu);
         assert_eq!(gs.provider.name, "Dota 2".to_owned());
     }
 
+    #[test]
+    fn test_empty_map_and_draft_deserialize_as_none() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            },
+            "map": {},
+            "draft": {}
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert!(gs.map.is_none());
+        assert!(gs.draft.is_none());
+        assert!(gs.to_summary_json()["clock_time"].is_null());
+    }
+
+    #[cfg(feature = "capture-extra")]
+    #[test]
+    fn test_game_state_extra() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            },
+            "map": {},
+            "player": {},
+            "roshan": {
+                "alive": true,
+                "health": 5500
+            }
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert_eq!(
+            gs.extra("roshan"),
+            Some(&serde_json::json!({"alive": true, "health": 5500}))
+        );
+        assert!(gs.extra("map").is_none());
+        assert!(gs.extra("player").is_none());
+        assert!(gs.extra("provider").is_none());
+
+        let serialized = serde_json::to_value(&gs).expect("Failed to serialize GameState");
+        assert_eq!(serialized["roshan"]["health"], 5500);
+    }
+
+    #[test]
+    fn test_game_state_default() {
+        let gs = GameState::default();
+
+        assert!(gs.get_provider().is_dota());
+        assert_eq!(gs.get_provider().timestamp(), 0);
+        assert_eq!(gs.mode(), GameMode::Menu);
+        assert!(gs.get_hero().is_none());
+
+        let playing = GameState {
+            map: Some(Map {
+                clock_time: 90,
+                game_state: DotaGameRulesState::InProgress,
+                ..Default::default()
+            }),
+            players: Some(GamePlayers::Playing(players::PlayerInformation {
+                activity: PlayerActivity::Playing,
+                gold: 625,
+                ..Default::default()
+            })),
+            heroes: Some(GameHeroes::Playing(Hero {
+                id: 42,
+                name: Some("npc_dota_hero_skeleton_king".to_owned()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        assert_eq!(playing.mode(), GameMode::Playing);
+        assert_eq!(playing.get_hero().map(|h| h.id), Some(42));
+
+        let summary = playing.to_summary_json();
+        assert_eq!(summary["clock_time"], 90);
+        assert_eq!(summary["gold"], 625);
+        assert_eq!(
+            summary["game_state"],
+            DotaGameRulesState::InProgress.to_string()
+        );
+
+        // Outside the crate, the same state is built with setters.
+        let built = GameState::default()
+            .with_map(
+                Map::default()
+                    .with_clock_time(90)
+                    .with_game_state(DotaGameRulesState::InProgress),
+            )
+            .with_players(GamePlayers::Playing(players::PlayerInformation {
+                activity: PlayerActivity::Playing,
+                gold: 625,
+                ..Default::default()
+            }))
+            .with_heroes(GameHeroes::Playing(Hero {
+                id: 42,
+                name: Some("npc_dota_hero_skeleton_king".to_owned()),
+                ..Default::default()
+            }));
+        assert_eq!(built, playing);
+
+        let provider = Provider::default()
+            .with_version(47)
+            .with_timestamp(1658690112);
+        assert!(provider.is_dota());
+        assert_eq!(provider.timestamp(), 1658690112);
+        assert_eq!(provider.to_string(), "Dota 2 47");
+    }
+
+    #[test]
+    fn test_game_state_abilities() {
+        let provider = r#""provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            }"#;
+        let ability = r#"{
+                "name": "skeleton_king_hellfire_blast",
+                "level": 1,
+                "can_cast": true,
+                "passive": false,
+                "ability_active": true,
+                "cooldown": 0,
+                "ultimate": false
+            }"#;
+
+        let playing: GameState = serde_json::from_str(&format!(
+            r#"{{{}, "abilities": {{"ability0": {}}}}}"#,
+            provider, ability
+        ))
+        .expect("Failed to deserialize GameState");
+        let abilities = playing.get_abilities().expect("missing abilities");
+        assert_eq!(abilities.len(), 1);
+        assert!(abilities
+            .values()
+            .all(|a| a.name() == "skeleton_king_hellfire_blast"));
+        assert!(playing
+            .get_team_player_abilities(&Team::Radiant, &PlayerID::from(0))
+            .is_none());
+
+        let sequential: GameState =
+            serde_json::from_str(&format!(r#"{{{}, "abilities": [{}]}}"#, provider, ability))
+                .expect("Failed to deserialize GameState");
+        assert_eq!(sequential.get_abilities().map(|a| a.len()), Some(1));
+
+        let spectating: GameState = serde_json::from_str(&format!(
+            r#"{{{}, "abilities": {{"team2": {{"player0": {{"ability0": {}}}}}}}}}"#,
+            provider, ability
+        ))
+        .expect("Failed to deserialize GameState");
+        assert!(spectating.get_abilities().is_none());
+        assert_eq!(
+            spectating
+                .get_team_player_abilities(&Team::Radiant, &PlayerID::from(0))
+                .map(|a| a.len()),
+            Some(1)
+        );
+        assert!(spectating
+            .get_team_player_abilities(&Team::Dire, &PlayerID::from(5))
+            .is_none());
+
+        let not_in_game: GameState =
+            serde_json::from_str(&format!(r#"{{{}, "abilities": {{}}}}"#, provider))
+                .expect("Failed to deserialize GameState");
+        assert!(not_in_game.get_abilities().is_none());
+        assert!(not_in_game
+            .get_team_player_abilities(&Team::Radiant, &PlayerID::from(0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_spectating_game_state_wearables() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            },
+            "wearables": {
+                "team3": {
+                    "player5": {
+                        "wearable0": 13773,
+                        "wearable1": 14451
+                    }
+                }
+            }
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert!(gs.get_wearables().is_none());
+        assert_eq!(
+            gs.get_team_player_wearables(&Team::Dire, &PlayerID::from(5))
+                .map(|w| w.len()),
+            Some(2)
+        );
+        assert!(gs
+            .get_team_player_wearables(&Team::Radiant, &PlayerID::from(0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_game_state_validate() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            },
+            "player": {},
+            "draft": {},
+            "items": {}
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        let expected = GsiData::builder()
+            .none()
+            .provider(true)
+            .player(true)
+            .draft(true)
+            .items(true)
+            .build();
+        assert_eq!(
+            gs.validate(&expected),
+            vec![
+                MissingComponent::Player,
+                MissingComponent::Items,
+                MissingComponent::Draft
+            ]
+        );
+
+        let nothing_expected = GsiData::builder().none().build();
+        assert!(gs.validate(&nothing_expected).is_empty());
+        assert_eq!(gs.validate(&GsiData::default()).len(), 8);
+    }
+
+    #[test]
+    fn test_spectating_game_state_mode() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1658690112
+            },
+            "player": {
+                "team2": {
+                    "player0": {
+                        "steamid": "76561198069076692",
+                        "name": "Nukkumatti",
+                        "activity": "playing",
+                        "kills": 2,
+                        "deaths": 3,
+                        "assists": 5,
+                        "last_hits": 8,
+                        "denies": 3,
+                        "kill_streak": 0,
+                        "kill_list": {},
+                        "commands_issued": 2138,
+                        "team_name": "radiant",
+                        "gold": 318,
+                        "gold_reliable": 102,
+                        "gold_unreliable": 216,
+                        "gold_from_hero_kills": 574,
+                        "gold_from_creep_kills": 288,
+                        "gold_from_income": 1351,
+                        "gold_from_shared": 252,
+                        "gpm": 202,
+                        "xpm": 248
+                    }
+                }
+            },
+            "couriers": {
+                "courier0": {
+                    "health": 75,
+                    "max_health": 75,
+                    "alive": true,
+                    "items": {},
+                    "owner": 0
+                }
+            }
+        }"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert_eq!(gs.mode(), GameMode::Spectating);
+        assert!(gs.is_spectating());
+        assert_eq!(gs.get_couriers().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_inititalizing_game_state_deserialize() {
         let json_str = r#"{
@@ -351,7 +1267,7 @@ mod tests {
             gs.map.unwrap().game_state,
             DotaGameRulesState::Starting
         ));
-        assert_eq!(buildings.is_empty(), false);
+        assert!(!buildings.is_empty());
         assert_eq!(buildings.len(), 2);
     }
 
@@ -427,11 +1343,28 @@ mod tests {
 }"#;
         let gs: GameState =
             serde_json::from_str(json_str).expect("Failed to deserialize GameState Strategy Time");
+        let map = gs.map.unwrap();
 
-        assert!(matches!(
-            gs.map.unwrap().game_state,
-            DotaGameRulesState::StrategyTime
-        ));
+        assert!(matches!(map.game_state, DotaGameRulesState::StrategyTime));
+        assert_eq!(map.ward_purchase_cooldown_duration(), Some(Duration::ZERO));
+        assert!(!map.is_ward_on_cooldown());
+
+        let on_cooldown = Map {
+            ward_purchase_cooldown: Some(135),
+            ..map.clone()
+        };
+        assert_eq!(
+            on_cooldown.ward_purchase_cooldown_duration(),
+            Some(Duration::from_secs(135))
+        );
+        assert!(on_cooldown.is_ward_on_cooldown());
+
+        let not_sent = Map {
+            ward_purchase_cooldown: None,
+            ..map
+        };
+        assert_eq!(not_sent.ward_purchase_cooldown_duration(), None);
+        assert!(!not_sent.is_ward_on_cooldown());
     }
 
     #[test]
@@ -642,6 +1575,16 @@ mod tests {
     },
     "neutral0": {
       "name": "empty"
+    },
+    "preserved_neutral6": {
+      "name": "item_trusty_shovel",
+      "purchaser": 0,
+      "can_cast": true,
+      "cooldown": 0,
+      "passive": false
+    },
+    "preserved_neutral7": {
+      "name": "empty"
     }
   },
   "draft": {},
@@ -678,6 +1621,10 @@ mod tests {
         assert!(matches!(heroes, GameHeroes::Playing(_)));
         if let GameHeroes::Playing(hero) = heroes {
             assert_eq!(hero.id, 42);
+            let position = hero.position().unwrap();
+            assert_eq!(position, Position::new(-1664, -1216));
+            assert_eq!(position.distance_to(&Position::new(-1664, -1216)), 0.0);
+            assert_eq!(position.distance_to(&Position::new(-1661, -1212)), 5.0);
         } else {
             panic!("Failed to deserialize single hero");
         }
@@ -688,9 +1635,91 @@ mod tests {
         } else {
             panic!("Failed to deserialize wearables");
         }
+        assert_eq!(gs.get_wearables().unwrap().len(), 12);
+        assert!(gs
+            .get_team_player_wearables(&Team::Radiant, &PlayerID::from(0))
+            .is_none());
 
         assert!(matches!(players, GamePlayers::Playing(_)));
-        assert!(gs.get_items().is_some());
+        let abilities = gs.abilities.as_ref().unwrap();
+        assert_eq!(abilities.abilities_in_order().len(), 6);
+        assert_eq!(
+            abilities
+                .gameplay_abilities()
+                .iter()
+                .map(|a| a.name())
+                .collect::<Vec<&str>>(),
+            vec![
+                "skeleton_king_hellfire_blast",
+                "skeleton_king_vampiric_aura",
+                "skeleton_king_mortal_strike",
+                "skeleton_king_reincarnation",
+            ]
+        );
+
+        let items = gs.get_items().unwrap();
+        assert_eq!(
+            items
+                .filled_inventory()
+                .filter_map(|slot| slot.item())
+                .map(|item| item.name())
+                .collect::<Vec<&str>>(),
+            vec!["item_manta", "item_ultimate_orb"]
+        );
+        assert_eq!(items.find("item_manta").unwrap().name(), "item_manta");
+        assert!(items.contains("item_tpscroll"));
+        assert!(!items.contains("item_black_king_bar"));
+        assert!(items.has_item_ready("item_manta"));
+        // Passive and on cooldown items are not ready.
+        assert!(!items.has_item_ready("item_ultimate_orb"));
+        assert!(!items.has_item_ready("item_tpscroll"));
+        // Neutral items from a previous tier are kept in preserved neutral slots.
+        assert!(items.contains("item_trusty_shovel"));
+        assert!(items.has_item_ready("item_trusty_shovel"));
+        assert!(matches!(
+            items.preserved_neutral_slot(6),
+            Some(items::ItemSlot::Full { index: 6, item: _ })
+        ));
+        assert!(matches!(
+            items.preserved_neutral_slot(7),
+            Some(items::ItemSlot::Empty { index: 7 })
+        ));
+        assert_eq!(gs.mode(), GameMode::Playing);
+        assert!(!gs.is_spectating());
+
+        // Neither events nor couriers are expected while playing, nor the draft once in game.
+        assert!(gs.validate(&GsiData::default()).is_empty());
+        let everything = GsiData::builder()
+            .events(true)
+            .minimap(true)
+            .couriers(true)
+            .build();
+        assert_eq!(gs.validate(&everything), vec![MissingComponent::Minimap]);
+
+        let same: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState In Progress");
+        assert_eq!(gs, same);
+        assert_eq!(gs.get_hero(), same.get_hero());
+
+        let mut later = same.clone();
+        later.map.as_mut().unwrap().clock_time += 1;
+        assert_ne!(gs, later);
+        assert_eq!(gs.provider, later.provider);
+
+        let flattened = gs.flatten();
+        assert_eq!(flattened.get("hero.health"), Some(&640.0));
+        assert_eq!(flattened.get("hero.alive"), Some(&0.0));
+        assert_eq!(flattened.get("player.gold"), Some(&600.0));
+        assert_eq!(flattened.get("map.game_time"), Some(&1.0));
+        assert_eq!(flattened.get("map.daytime"), Some(&1.0));
+        assert_eq!(flattened.get("abilities.ability4.level"), Some(&1.0));
+        assert_eq!(
+            flattened.get("buildings.radiant.dota_goodguys_tower1_mid.health"),
+            Some(&1800.0)
+        );
+        // Strings and fields missing from the event are skipped.
+        assert!(!flattened.contains_key("hero.name"));
+        assert!(!flattened.contains_key("map.radiant_score"));
     }
 
     #[test]
@@ -715,9 +1744,271 @@ mod tests {
         assert_eq!(map.match_id, "0");
         assert_eq!(map.game_time, 5);
         assert_eq!(map.clock_time, 4);
-        assert_eq!(map.daytime, true);
-        assert_eq!(map.nightstalker_night, false);
+        assert!(map.daytime);
+        assert!(!map.nightstalker_night);
         assert!(matches!(map.game_state, DotaGameRulesState::InProgress));
-        assert_eq!(map.paused, false);
+        assert!(!map.paused);
+        assert!(map.winner().is_none());
+        assert!(!map.is_finished());
+        assert_eq!(map.game_time_duration(), Duration::from_secs(5));
+        assert_eq!(map.clock_time_duration(), Some(Duration::from_secs(4)));
+        assert_eq!(map.time_of_day(), TimeOfDay::Day);
+        assert_eq!(map.seconds_until_cycle_change(), Some(296));
+    }
+
+    #[test]
+    fn test_game_rules_state_from_str() {
+        assert!(matches!(
+            DotaGameRulesState::from("DOTA_GAMERULES_STATE_SCENARIO_SETUP".to_string()),
+            DotaGameRulesState::ScenarioSetup
+        ));
+        assert!(matches!(
+            DotaGameRulesState::from("DOTA_GAMERULES_STATE_TEAM_SHOWCASE".to_string()),
+            DotaGameRulesState::TeamShowcase
+        ));
+        assert!(matches!(
+            DotaGameRulesState::from("DOTA_GAMERULES_STATE_SOMETHING_NEW".to_string()),
+            DotaGameRulesState::Undefined(_)
+        ));
+        assert_eq!(
+            DotaGameRulesState::TeamShowcase.to_string(),
+            "Team Showcase"
+        );
+
+        assert!(DotaGameRulesState::InProgress.is_in_game());
+        assert!(DotaGameRulesState::StrategyTime.is_in_game());
+        assert!(DotaGameRulesState::PreGame.is_in_game());
+        assert!(!DotaGameRulesState::HeroSelection.is_in_game());
+        assert!(!DotaGameRulesState::PostGame.is_in_game());
+    }
+
+    #[test]
+    fn test_map_finished() {
+        let json_str = r#"{
+            "name": "start",
+            "matchid": "7206545236",
+            "game_time": 2410,
+            "clock_time": 2312,
+            "daytime": true,
+            "nightstalker_night": false,
+            "game_state": "DOTA_GAMERULES_STATE_POST_GAME",
+            "paused": false,
+            "win_team": "dire",
+            "customgamename": "",
+            "ward_purchase_cooldown": 0
+        }"#;
+
+        let map: Map = serde_json::from_str(json_str).expect("Failed to deserialize Map");
+
+        assert_eq!(map.winner(), Some(Team::Dire));
+        assert!(map.is_finished());
+
+        let json_str = r#"{
+            "name": "start",
+            "matchid": "7206545236",
+            "game_time": 30,
+            "clock_time": -60,
+            "daytime": true,
+            "nightstalker_night": false,
+            "game_state": "DOTA_GAMERULES_STATE_PRE_GAME",
+            "paused": false,
+            "win_team": "none",
+            "customgamename": "",
+            "ward_purchase_cooldown": 0
+        }"#;
+
+        let map: Map = serde_json::from_str(json_str).expect("Failed to deserialize Map");
+
+        assert!(map.winner().is_none());
+        assert!(!map.is_finished());
+        assert_eq!(map.clock_time_duration(), None);
+        assert_eq!(map.seconds_until_cycle_change(), None);
+    }
+
+    #[test]
+    fn test_map_time_of_day() {
+        let json_str = r#"{
+            "name": "start",
+            "matchid": "7206545236",
+            "game_time": 700,
+            "clock_time": 600,
+            "daytime": false,
+            "nightstalker_night": false,
+            "game_state": "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS",
+            "paused": false,
+            "win_team": "none",
+            "customgamename": "",
+            "ward_purchase_cooldown": 0
+        }"#;
+
+        let mut map: Map = serde_json::from_str(json_str).expect("Failed to deserialize Map");
+
+        assert_eq!(map.time_of_day(), TimeOfDay::Night);
+        assert_eq!(map.seconds_until_cycle_change(), Some(300));
+
+        map.clock_time = 899;
+        map.daytime = true;
+        map.nightstalker_night = true;
+        assert_eq!(map.time_of_day(), TimeOfDay::Night);
+        assert_eq!(map.seconds_until_cycle_change(), Some(1));
+    }
+
+    #[test]
+    fn test_map_next_runes() {
+        let json_str = r#"{
+            "name": "start",
+            "matchid": "7206545236",
+            "game_time": 50,
+            "clock_time": -40,
+            "daytime": true,
+            "nightstalker_night": false,
+            "game_state": "DOTA_GAMERULES_STATE_PRE_GAME",
+            "paused": false,
+            "win_team": "none",
+            "customgamename": "",
+            "ward_purchase_cooldown": 0
+        }"#;
+
+        let mut map: Map = serde_json::from_str(json_str).expect("Failed to deserialize Map");
+
+        assert_eq!(map.next_bounty_rune(), None);
+        assert_eq!(map.next_power_rune(), None);
+
+        for (clock_time, bounty, power) in [
+            (0, 180, 360),
+            (100, 80, 260),
+            (359, 1, 1),
+            (360, 180, 120),
+            (545, 175, 55),
+            (1200, 60, 120),
+        ] {
+            map.clock_time = clock_time;
+            assert_eq!(map.next_bounty_rune(), Some(bounty));
+            assert_eq!(map.next_power_rune(), Some(power));
+        }
+
+        assert_eq!(map.roshan_respawn_window(1312), (1792, 1972));
+    }
+
+    #[test]
+    fn test_provider_is_dota() {
+        let dota: Provider = serde_json::from_str(
+            r#"{"name": "Dota 2", "appid": 570, "version": 47, "timestamp": 1688514013}"#,
+        )
+        .expect("Failed to deserialize Provider");
+        let csgo: Provider = serde_json::from_str(
+            r#"{"name": "Counter-Strike: Global Offensive", "appid": 730, "version": 13, "timestamp": 1688514013}"#,
+        )
+        .expect("Failed to deserialize Provider");
+
+        assert_eq!(dota.app_id(), DOTA_APP_ID);
+        assert!(dota.is_dota());
+        assert!(!csgo.is_dota());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_provider_datetime() {
+        let json_str = r#"{
+            "name": "Dota 2",
+            "appid": 570,
+            "version": 47,
+            "timestamp": 1688514013
+        }"#;
+
+        let provider: Provider =
+            serde_json::from_str(json_str).expect("Failed to deserialize Provider");
+
+        assert_eq!(
+            provider.datetime().to_rfc3339(),
+            "2023-07-04T23:40:13+00:00"
+        );
+        assert_eq!(provider.datetime().timestamp(), 1688514013);
+    }
+
+    #[test]
+    fn test_game_state_to_summary_json() {
+        let json_str = r#"{
+  "provider": {
+    "name": "Dota 2",
+    "appid": 570,
+    "version": 47,
+    "timestamp": 1659035016
+  },
+  "map": {
+    "name": "hero_demo_main",
+    "matchid": "0",
+    "game_time": 1,
+    "clock_time": 0,
+    "daytime": true,
+    "nightstalker_night": false,
+    "radiant_score": 3,
+    "dire_score": 5,
+    "game_state": "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS",
+    "paused": false,
+    "win_team": "none",
+    "customgamename": "/home/tomasfarias/.local/share/Steam/steamapps/common/dota 2 beta/game/dota_addons/hero_demo",
+    "ward_purchase_cooldown": 0
+  },
+  "player": {
+    "steamid": "76561197996881999",
+    "name": "farxc3xadas",
+    "activity": "playing",
+    "kills": 0,
+    "deaths": 0,
+    "assists": 0,
+    "last_hits": 0,
+    "denies": 0,
+    "kill_streak": 0,
+    "commands_issued": 0,
+    "kill_list": {},
+    "team_name": "radiant",
+    "gold": 600,
+    "gold_reliable": 0,
+    "gold_unreliable": 600,
+    "gold_from_hero_kills": 0,
+    "gold_from_creep_kills": 0,
+    "gold_from_income": 0,
+    "gold_from_shared": 0,
+    "gpm": 0,
+    "xpm": 0
+  },
+  "hero": {
+    "xpos": -1664,
+    "ypos": -1216,
+    "id": 42,
+    "name": "npc_dota_hero_skeleton_king",
+    "level": 0,
+    "alive": true,
+    "health": 640,
+    "max_health": 640
+  }
+}"#;
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert_eq!(
+            gs.to_summary_json(),
+            serde_json::json!({
+                "clock_time": 0,
+                "game_state": "In Progress",
+                "radiant_score": 3,
+                "dire_score": 5,
+                "hero_name": "npc_dota_hero_skeleton_king",
+                "hero_health": 640,
+                "hero_max_health": 640,
+                "gold": 600
+            })
+        );
+
+        let idle: GameState = serde_json::from_str(
+            r#"{"provider": {"name": "Dota 2", "appid": 570, "version": 47, "timestamp": 1}}"#,
+        )
+        .expect("Failed to deserialize GameState");
+        let summary = idle.to_summary_json();
+
+        assert_eq!(summary.as_object().unwrap().len(), 8);
+        assert!(summary["clock_time"].is_null());
+        assert!(summary["hero_name"].is_null());
     }
 }
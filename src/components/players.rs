@@ -12,11 +12,22 @@ pub enum PlayersError {
     ParseIDError(String),
     #[error("attempted to parse an empty player")]
     EmptyPlayer,
+    #[error("expected a single player but found {0}")]
+    MultiplePlayers(usize),
+    #[error("player ID {0} is out of range, expected 0 to {max}", max = MAX_PLAYER_ID)]
+    IDOutOfRange(u8),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Player IDs are the slots 0 to 9: 0 to 4 are Radiant, and 5 to 9 are Dire.
+const MAX_PLAYER_ID: u8 = 9;
+
+/// The first player ID in the Dire team.
+const FIRST_DIRE_PLAYER_ID: u8 = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(from = "String")]
 pub enum PlayerActivity {
+    #[default]
     Menu,
     Playing,
     Undefined(String),
@@ -24,11 +35,11 @@ pub enum PlayerActivity {
 
 impl From<String> for PlayerActivity {
     fn from(s: String) -> Self {
-        return match s.as_str() {
+        match s.as_str() {
             "menu" => PlayerActivity::Menu,
             "playing" => PlayerActivity::Playing,
             _ => PlayerActivity::Undefined(s),
-        };
+        }
     }
 }
 
@@ -42,9 +53,30 @@ impl fmt::Display for PlayerActivity {
     }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub struct PlayerID(u8);
 
+impl PlayerID {
+    /// Create a PlayerID, rejecting numbers outside of the valid slots 0 to 9.
+    pub fn new(n: u8) -> Result<PlayerID, PlayersError> {
+        if n > MAX_PLAYER_ID {
+            return Err(PlayersError::IDOutOfRange(n));
+        }
+
+        Ok(PlayerID(n))
+    }
+
+    /// The team playing in this slot: 0 to 4 are Radiant, and 5 to 9 are Dire.
+    pub fn team(&self) -> Team {
+        if self.0 < FIRST_DIRE_PLAYER_ID {
+            Team::Radiant
+        } else {
+            Team::Dire
+        }
+    }
+}
+
+/// Unchecked conversion, mostly for tests. Use [`PlayerID::new`] to validate the number.
 impl From<u8> for PlayerID {
     fn from(n: u8) -> Self {
         PlayerID(n)
@@ -61,7 +93,7 @@ impl<'de> Deserialize<'de> for PlayerID {
 
         if let (_, Some(index_res)) = (slot_split.next(), slot_split.next()) {
             let index = index_res.map_err(D::Error::custom)?;
-            return Ok(PlayerID(index));
+            return PlayerID::new(index).map_err(D::Error::custom);
         }
 
         Err(D::Error::custom(PlayersError::ParseIDError(s)))
@@ -77,13 +109,13 @@ impl Serialize for PlayerID {
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct Player {
     pub id: String,
     pub information: PlayerInformation,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct PlayerInformation {
     pub steamid: String,
     pub name: String,
@@ -95,18 +127,47 @@ pub struct PlayerInformation {
     pub denies: u16,
     pub kill_streak: u16,
     pub kill_list: HashMap<String, u32>,
-    pub commands_issued: u32,
+    pub commands_issued: u64,
     pub team_name: Team,
-    pub gold: u32,
-    pub gold_reliable: u32,
-    pub gold_unreliable: u32,
-    pub gold_from_hero_kills: u32,
-    pub gold_from_creep_kills: u32,
-    pub gold_from_income: u32,
-    pub gold_from_shared: u32,
-    pub net_worth: Option<u32>,
+    pub gold: u64,
+    pub gold_reliable: u64,
+    pub gold_unreliable: u64,
+    pub gold_from_hero_kills: u64,
+    pub gold_from_creep_kills: u64,
+    pub gold_from_income: u64,
+    pub gold_from_shared: u64,
+    pub net_worth: Option<u64>,
     pub gpm: u32,
     pub xpm: u32,
+    // The following are only sent when spectating.
+    #[serde(default)]
+    pub camps_stacked: Option<u32>,
+    #[serde(default)]
+    pub consumable_gold_spent: Option<u64>,
+    #[serde(default)]
+    pub gold_lost_to_death: Option<u64>,
+    #[serde(default)]
+    pub gold_spent_on_buybacks: Option<u64>,
+    #[serde(default)]
+    pub hero_damage: Option<u32>,
+    #[serde(default)]
+    pub item_gold_spent: Option<u64>,
+    #[serde(default)]
+    pub runes_activated: Option<u32>,
+    #[serde(default)]
+    pub support_gold_spent: Option<u64>,
+    #[serde(default)]
+    pub wards_destroyed: Option<u32>,
+    #[serde(default)]
+    pub wards_placed: Option<u32>,
+    #[serde(default)]
+    pub wards_purchased: Option<u32>,
+}
+
+impl Player {
+    pub fn into_information(self) -> PlayerInformation {
+        self.information
+    }
 }
 
 impl fmt::Display for Player {
@@ -120,11 +181,16 @@ impl<'de> Deserialize<'de> for Player {
     where
         D: de::Deserializer<'de>,
     {
-        let mut helper: HashMap<String, PlayerInformation> = HashMap::deserialize(deserializer)?;
+        let helper: HashMap<String, PlayerInformation> = HashMap::deserialize(deserializer)?;
+
+        if helper.len() > 1 {
+            return Err(D::Error::custom(PlayersError::MultiplePlayers(
+                helper.len(),
+            )));
+        }
 
         let (k, v) = helper
-            .drain()
-            .take(1)
+            .into_iter()
             .next()
             .ok_or(PlayersError::EmptyPlayer)
             .map_err(D::Error::custom)?;
@@ -136,8 +202,9 @@ impl<'de> Deserialize<'de> for Player {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum GamePlayers {
     Spectating(HashMap<Team, HashMap<PlayerID, PlayerInformation>>),
     Playing(PlayerInformation),
@@ -533,6 +600,129 @@ mod tests {
             serde_json::from_str(json_str).expect("Failed to deserialize Players");
 
         assert!(matches!(players, GamePlayers::Spectating(_)));
+
+        if let GamePlayers::Spectating(teams) = players {
+            let player = &teams[&Team::Radiant][&PlayerID::from(0)];
+
+            assert_eq!(player.hero_damage, Some(2725));
+            assert_eq!(player.wards_placed, Some(3));
+            assert_eq!(player.wards_purchased, Some(6));
+            assert_eq!(player.camps_stacked, Some(2));
+            assert_eq!(player.support_gold_spent, Some(250));
+        }
+    }
+
+    #[test]
+    fn test_spectating_players_team_keys() {
+        // Map keys go through Team's From<String>, like values, so both key styles are accepted.
+        for (radiant, dire) in [("team2", "team3"), ("radiant", "dire")] {
+            let json_str = format!(
+                r#"{{"{}": {{"player0": {}}}, "{}": {{"player5": {}}}}}"#,
+                radiant, PLAYER_INFORMATION, dire, PLAYER_INFORMATION
+            );
+
+            let players: GamePlayers =
+                serde_json::from_str(&json_str).expect("Failed to deserialize Players");
+
+            match players {
+                GamePlayers::Spectating(teams) => {
+                    assert_eq!(teams.len(), 2);
+                    assert!(teams[&Team::Radiant].contains_key(&PlayerID::from(0)));
+                    assert!(teams[&Team::Dire].contains_key(&PlayerID::from(5)));
+                }
+                GamePlayers::Playing(_) => panic!("Expected spectating players"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_player_id_range() {
+        assert_eq!(PlayerID::new(9).unwrap(), PlayerID::from(9));
+        assert!(matches!(
+            PlayerID::new(10),
+            Err(PlayersError::IDOutOfRange(10))
+        ));
+
+        let id: PlayerID =
+            serde_json::from_str("\"player9\"").expect("Failed to deserialize PlayerID");
+        assert_eq!(id, PlayerID::from(9));
+        assert!(serde_json::from_str::<PlayerID>("\"player10\"").is_err());
+        assert!(serde_json::from_str::<PlayerID>("\"player42\"").is_err());
+    }
+
+    #[test]
+    fn test_player_id_team() {
+        assert_eq!(PlayerID::from(0).team(), Team::Radiant);
+        assert_eq!(PlayerID::from(4).team(), Team::Radiant);
+        assert_eq!(PlayerID::from(5).team(), Team::Dire);
+        assert_eq!(PlayerID::from(9).team(), Team::Dire);
+    }
+
+    #[test]
+    fn test_player_information_without_spectator_stats() {
+        let information: PlayerInformation = serde_json::from_str(PLAYER_INFORMATION)
+            .expect("Failed to deserialize PlayerInformation");
+
+        assert!(information.hero_damage.is_none());
+        assert!(information.wards_placed.is_none());
+    }
+
+    const PLAYER_INFORMATION: &str = r#"{
+        "steamid": "76561197996881999",
+        "name": "farxc3xadas",
+        "activity": "playing",
+        "kills": 0,
+        "deaths": 0,
+        "assists": 0,
+        "last_hits": 0,
+        "denies": 0,
+        "kill_streak": 0,
+        "commands_issued": 0,
+        "kill_list": {},
+        "team_name": "radiant",
+        "gold": 600,
+        "gold_reliable": 0,
+        "gold_unreliable": 600,
+        "gold_from_hero_kills": 0,
+        "gold_from_creep_kills": 0,
+        "gold_from_income": 0,
+        "gold_from_shared": 0,
+        "gpm": 0,
+        "xpm": 0
+    }"#;
+
+    #[test]
+    fn test_player_deserialize() {
+        let json_str = format!(r#"{{"player0": {}}}"#, PLAYER_INFORMATION);
+
+        let player: Player = serde_json::from_str(&json_str).expect("Failed to deserialize Player");
+
+        assert_eq!(player.id, "player0");
+        assert_eq!(player.into_information().name, "farxc3xadas");
+    }
+
+    #[test]
+    fn test_player_deserialize_empty() {
+        let result: Result<Player, _> = serde_json::from_str("{}");
+
+        let error = result.expect_err("empty player should fail to deserialize");
+        assert_eq!(error.to_string(), PlayersError::EmptyPlayer.to_string());
+    }
+
+    #[test]
+    fn test_player_deserialize_multiple() {
+        let json_str = format!(
+            r#"{{"player0": {}, "player1": {}}}"#,
+            PLAYER_INFORMATION, PLAYER_INFORMATION
+        );
+
+        let result: Result<Player, _> = serde_json::from_str(&json_str);
+
+        let error = result.expect_err("multiple players should fail to deserialize");
+        assert_eq!(
+            error.to_string(),
+            PlayersError::MultiplePlayers(2).to_string()
+        );
     }
 
     #[test]
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use super::heroes::Hero;
+use super::items::Items;
+use super::players::{PlayerID, PlayerInformation};
+use super::{GameState, Team};
+
+/// A player of a spectated game, joined with their hero and items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerRow<'a> {
+    pub id: PlayerID,
+    pub information: &'a PlayerInformation,
+    pub hero: Option<&'a Hero>,
+    pub items: Option<&'a Items>,
+}
+
+/// Every player of a spectated game by team, sorted by their slot.
+/// See [`GameState::scoreboard`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scoreboard<'a> {
+    pub radiant: Vec<PlayerRow<'a>>,
+    pub dire: Vec<PlayerRow<'a>>,
+}
+
+impl<'a> Scoreboard<'a> {
+    pub(crate) fn new(
+        gs: &'a GameState,
+        players: &'a HashMap<Team, HashMap<PlayerID, PlayerInformation>>,
+    ) -> Self {
+        Scoreboard {
+            radiant: team_rows(gs, players, &Team::Radiant),
+            dire: team_rows(gs, players, &Team::Dire),
+        }
+    }
+
+    /// The rows of the given team. Only Radiant and Dire have rows.
+    pub fn team(&self, team: &Team) -> &[PlayerRow<'a>] {
+        match team {
+            Team::Radiant => &self.radiant,
+            Team::Dire => &self.dire,
+            _ => &[],
+        }
+    }
+}
+
+fn team_rows<'a>(
+    gs: &'a GameState,
+    players: &'a HashMap<Team, HashMap<PlayerID, PlayerInformation>>,
+    team: &Team,
+) -> Vec<PlayerRow<'a>> {
+    let mut rows: Vec<PlayerRow> = players
+        .get(team)
+        .into_iter()
+        .flatten()
+        .map(|(id, information)| PlayerRow {
+            id: id.clone(),
+            information,
+            hero: gs.get_team_player_hero(team, id),
+            items: gs.get_team_player_items(team, id),
+        })
+        .collect();
+
+    rows.sort_by(|a, b| a.id.cmp(&b.id));
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER_INFORMATION: &str = r#"{
+        "activity": "playing",
+        "assists": 0,
+        "commands_issued": 10,
+        "deaths": 0,
+        "denies": 0,
+        "gold": 600,
+        "gold_from_creep_kills": 0,
+        "gold_from_hero_kills": 0,
+        "gold_from_income": 0,
+        "gold_from_shared": 0,
+        "gold_reliable": 0,
+        "gold_unreliable": 600,
+        "gpm": 0,
+        "kill_list": {},
+        "kill_streak": 0,
+        "kills": 0,
+        "last_hits": 0,
+        "name": "NAME",
+        "steamid": "76561198069076692",
+        "team_name": "TEAM",
+        "xpm": 0
+    }"#;
+
+    fn player(name: &str, team: &str) -> String {
+        PLAYER_INFORMATION
+            .replace("NAME", name)
+            .replace("TEAM", team)
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        let json_str = format!(
+            r#"{{
+            "provider": {{
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1688514013
+            }},
+            "player": {{
+                "team2": {{
+                    "player1": {},
+                    "player0": {}
+                }},
+                "team3": {{
+                    "player5": {}
+                }}
+            }},
+            "hero": {{
+                "team2": {{
+                    "player0": {{"id": 42, "name": "npc_dota_hero_skeleton_king"}},
+                    "player1": {{"id": 1, "name": "npc_dota_hero_antimage"}}
+                }},
+                "team3": {{
+                    "player5": {{"id": 2, "name": "npc_dota_hero_axe"}}
+                }}
+            }},
+            "items": {{
+                "team2": {{
+                    "player0": {{
+                        "slot0": {{"name": "item_tango", "purchaser": 0, "passive": false}},
+                        "teleport0": {{"name": "empty"}},
+                        "neutral0": {{"name": "empty"}}
+                    }}
+                }}
+            }}
+        }}"#,
+            player("Second", "radiant"),
+            player("First", "radiant"),
+            player("Third", "dire"),
+        );
+
+        let gs: GameState =
+            serde_json::from_str(&json_str).expect("Failed to deserialize GameState");
+        let scoreboard = gs.scoreboard().expect("missing scoreboard");
+
+        let radiant: Vec<&str> = scoreboard
+            .radiant
+            .iter()
+            .map(|r| r.information.name.as_str())
+            .collect();
+        assert_eq!(radiant, vec!["First", "Second"]);
+        assert_eq!(scoreboard.dire.len(), 1);
+        assert_eq!(scoreboard.team(&Team::Dire)[0].id, PlayerID::from(5));
+        assert!(scoreboard.team(&Team::None).is_empty());
+
+        let first = &scoreboard.radiant[0];
+        assert_eq!(first.hero.unwrap().id, 42);
+        assert!(first.items.unwrap().contains("item_tango"));
+
+        let third = &scoreboard.dire[0];
+        assert_eq!(
+            third.hero.unwrap().name.as_deref(),
+            Some("npc_dota_hero_axe")
+        );
+        assert!(third.items.is_none());
+    }
+
+    #[test]
+    fn test_scoreboard_not_spectating() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1688514013
+            }
+        }"#;
+
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+
+        assert!(gs.scoreboard().is_none());
+    }
+}
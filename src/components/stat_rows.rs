@@ -0,0 +1,212 @@
+//! Flatten game states into rows of player statistics, enabled with the `csv` feature.
+use std::borrow::Borrow;
+use std::io;
+
+use serde::Serialize;
+
+use super::heroes::Hero;
+use super::players::{GamePlayers, PlayerID, PlayerInformation};
+use super::team::Team;
+use super::GameState;
+
+/// The statistics of a player at the time of an event, as a row of a CSV file.
+///
+/// Columns are written in the order of the fields: `timestamp`, `clock_time`, `team`,
+/// `player_id`, `player_name`, `hero`, `kills`, `deaths`, `assists`, `net_worth`, `gpm`
+/// and `xpm`. Missing values are written as empty fields.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlayerStatRow {
+    /// Unix timestamp of the event, in seconds.
+    pub timestamp: u32,
+    /// Seconds in the game clock, negative before the horn.
+    pub clock_time: Option<i32>,
+    pub team: Team,
+    /// Only known when spectating, like `player3`.
+    pub player_id: Option<PlayerID>,
+    pub player_name: String,
+    /// The internal name of the hero, like `npc_dota_hero_axe`.
+    pub hero: Option<String>,
+    pub kills: u16,
+    pub deaths: u16,
+    pub assists: u16,
+    pub net_worth: Option<u64>,
+    pub gpm: u32,
+    pub xpm: u32,
+}
+
+impl PlayerStatRow {
+    fn new(
+        gs: &GameState,
+        player_id: Option<PlayerID>,
+        information: &PlayerInformation,
+        hero: Option<&Hero>,
+    ) -> Self {
+        PlayerStatRow {
+            timestamp: gs.provider.timestamp(),
+            clock_time: gs.map.as_ref().map(|m| m.clock_time),
+            team: information.team_name.clone(),
+            player_id,
+            player_name: information.name.clone(),
+            hero: hero.and_then(|h| h.name.clone()),
+            kills: information.kills,
+            deaths: information.deaths,
+            assists: information.assists,
+            net_worth: information.net_worth,
+            gpm: information.gpm,
+            xpm: information.xpm,
+        }
+    }
+}
+
+impl GameState {
+    /// One row per player in this event: every player when spectating, sorted by team and
+    /// slot, or only the player being played. Empty in the menu.
+    pub fn to_csv_rows(&self) -> Vec<PlayerStatRow> {
+        match &self.players {
+            Some(GamePlayers::Playing(p)) => {
+                vec![PlayerStatRow::new(self, None, p, self.get_hero())]
+            }
+            Some(GamePlayers::Spectating(_)) => self
+                .scoreboard()
+                .into_iter()
+                .flat_map(|scoreboard| scoreboard.radiant.into_iter().chain(scoreboard.dire))
+                .map(|row| PlayerStatRow::new(self, Some(row.id), row.information, row.hero))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Write the rows of every state into a single CSV.
+/// A header row is written before the first row, so nothing is written without rows.
+pub fn write_csv<W, I>(writer: W, states: I) -> Result<(), csv::Error>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: Borrow<GameState>,
+{
+    let mut writer = csv::Writer::from_writer(writer);
+
+    for state in states {
+        for row in state.borrow().to_csv_rows() {
+            writer.serialize(row)?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::components::heroes::GameHeroes;
+    use crate::components::{Map, Provider};
+
+    fn player(name: &str, team: Team) -> PlayerInformation {
+        PlayerInformation {
+            name: name.to_owned(),
+            team_name: team,
+            kills: 1,
+            deaths: 2,
+            assists: 3,
+            net_worth: Some(5000),
+            gpm: 450,
+            xpm: 500,
+            ..Default::default()
+        }
+    }
+
+    fn provider() -> Provider {
+        Provider::default().with_timestamp(1688514013)
+    }
+
+    fn spectating() -> GameState {
+        let players = HashMap::from([
+            (
+                Team::Dire,
+                HashMap::from([(PlayerID::from(5), player("Second", Team::Dire))]),
+            ),
+            (
+                Team::Radiant,
+                HashMap::from([(PlayerID::from(0), player("First", Team::Radiant))]),
+            ),
+        ]);
+        let axe = Hero {
+            id: 2,
+            name: Some("npc_dota_hero_axe".to_owned()),
+            ..Default::default()
+        };
+        let heroes = HashMap::from([(Team::Radiant, HashMap::from([(PlayerID::from(0), axe)]))]);
+
+        GameState::default()
+            .with_provider(provider())
+            .with_map(Map::default().with_game_time(700).with_clock_time(600))
+            .with_players(GamePlayers::Spectating(players))
+            .with_heroes(GameHeroes::Spectating(heroes))
+    }
+
+    #[test]
+    fn test_to_csv_rows() {
+        let rows = spectating().to_csv_rows();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            PlayerStatRow {
+                timestamp: 1688514013,
+                clock_time: Some(600),
+                team: Team::Radiant,
+                player_id: Some(PlayerID::from(0)),
+                player_name: "First".to_owned(),
+                hero: Some("npc_dota_hero_axe".to_owned()),
+                kills: 1,
+                deaths: 2,
+                assists: 3,
+                net_worth: Some(5000),
+                gpm: 450,
+                xpm: 500,
+            }
+        );
+        assert_eq!(rows[1].team, Team::Dire);
+        assert_eq!(rows[1].hero, None);
+
+        let playing = GameState::default()
+            .with_provider(provider())
+            .with_players(GamePlayers::Playing(player("Solo", Team::Radiant)));
+        let rows = playing.to_csv_rows();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].player_id, None);
+        assert_eq!(rows[0].clock_time, None);
+
+        let menu = GameState::default().with_provider(provider());
+        assert!(menu.to_csv_rows().is_empty());
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut output = Vec::new();
+        write_csv(&mut output, [spectating(), spectating()]).expect("Failed to write CSV");
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "timestamp,clock_time,team,player_id,player_name,hero,kills,deaths,assists,net_worth,gpm,xpm"
+        );
+        assert_eq!(
+            lines[1],
+            "1688514013,600,radiant,player0,First,npc_dota_hero_axe,1,2,3,5000,450,500"
+        );
+        assert_eq!(
+            lines[2],
+            "1688514013,600,dire,player5,Second,,1,2,3,5000,450,500"
+        );
+    }
+}
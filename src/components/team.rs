@@ -1,24 +1,65 @@
 use std::fmt;
 
-use serde::{Deserialize, Serialize};
+use serde::{ser, Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Hash, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Hash, Eq, PartialEq, Clone, Default)]
 #[serde(from = "String")]
 pub enum Team {
     Radiant,
     Dire,
+    #[default]
     None,
     Undefined(String),
 }
 
+impl Team {
+    /// The name Dota uses for this team.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Team::Radiant => "radiant",
+            Team::Dire => "dire",
+            Team::None => "none",
+            Team::Undefined(s) => s,
+        }
+    }
+
+    /// The two teams playing a game, Radiant first.
+    pub fn playing() -> [Team; 2] {
+        [Team::Radiant, Team::Dire]
+    }
+
+    /// Whether this team is one of the two teams playing a game.
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Team::Radiant | Team::Dire)
+    }
+
+    /// The team playing against this team. Only Radiant and Dire have an opponent.
+    pub fn opponent(&self) -> Option<Team> {
+        match self {
+            Team::Radiant => Some(Team::Dire),
+            Team::Dire => Some(Team::Radiant),
+            _ => None,
+        }
+    }
+}
+
 impl From<String> for Team {
     fn from(s: String) -> Self {
-        return match s.as_str() {
+        match s.as_str() {
             "radiant" | "team2" => Team::Radiant,
             "dire" | "team3" => Team::Dire,
             "none" => Team::None,
             _ => Team::Undefined(s),
-        };
+        }
+    }
+}
+
+impl Serialize for Team {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -42,4 +83,40 @@ mod tests {
         assert!(matches!(Team::from("radiant".to_string()), Team::Radiant));
         assert!(matches!(Team::from("dire".to_string()), Team::Dire));
     }
+
+    #[test]
+    fn test_team_opponent() {
+        assert_eq!(Team::Radiant.opponent(), Some(Team::Dire));
+        assert_eq!(Team::Dire.opponent(), Some(Team::Radiant));
+        assert_eq!(Team::None.opponent(), None);
+        assert_eq!(Team::Undefined("team4".to_string()).opponent(), None);
+    }
+
+    #[test]
+    fn test_team_playing() {
+        assert_eq!(Team::playing(), [Team::Radiant, Team::Dire]);
+        assert!(Team::playing().iter().all(|t| t.is_playing()));
+        assert!(Team::playing()
+            .iter()
+            .all(|t| t.opponent().is_some_and(|o| o.is_playing())));
+        assert!(!Team::None.is_playing());
+        assert!(!Team::Undefined("spectator".to_string()).is_playing());
+    }
+
+    #[test]
+    fn test_team_serialize_round_trip() {
+        for name in ["radiant", "dire", "none", "neutrals"] {
+            let team = Team::from(name.to_string());
+            let serialized = serde_json::to_string(&team).expect("Failed to serialize Team");
+
+            assert_eq!(serialized, format!("\"{}\"", name));
+            assert_eq!(
+                serde_json::from_str::<Team>(&serialized).expect("Failed to deserialize Team"),
+                team
+            );
+        }
+
+        let team2 = serde_json::to_string(&Team::from("team2".to_string())).unwrap();
+        assert_eq!(team2, "\"radiant\"");
+    }
 }
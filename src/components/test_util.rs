@@ -0,0 +1,183 @@
+//! Build game states for tests without writing JSON, enabled with the `test-util` feature.
+use super::heroes::{GameHeroes, Hero};
+use super::players::{GamePlayers, PlayerActivity, PlayerInformation};
+use super::team::Team;
+use super::{DotaGameRulesState, GameState, Map};
+
+/// Builds a [`GameState`] as sent to a player, starting from the menu.
+///
+/// ```
+/// use dota::components::test_util::GameStateBuilder;
+///
+/// let gs = GameStateBuilder::mid_game_radiant()
+///     .with_player_kills(12)
+///     .build();
+/// assert!(gs.get_hero().is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GameStateBuilder {
+    state: GameState,
+}
+
+impl GameStateBuilder {
+    /// A state in the menu, with only a provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A player on Radiant twenty minutes into a game, playing Wraith King.
+    pub fn mid_game_radiant() -> Self {
+        Self::new()
+            .with_game_rules_state(DotaGameRulesState::InProgress)
+            .with_clock_time(1200)
+            .with_player(PlayerInformation {
+                name: "Radiant Player".to_owned(),
+                activity: PlayerActivity::Playing,
+                team_name: Team::Radiant,
+                kills: 5,
+                deaths: 2,
+                assists: 8,
+                last_hits: 140,
+                denies: 9,
+                gold: 1850,
+                net_worth: Some(11200),
+                gpm: 520,
+                xpm: 610,
+                ..Default::default()
+            })
+            .with_hero(Hero {
+                id: 42,
+                name: Some("npc_dota_hero_skeleton_king".to_owned()),
+                level: Some(14),
+                alive: Some(true),
+                health: Some(1650),
+                max_health: Some(2100),
+                health_percent: Some(78),
+                ..Default::default()
+            })
+    }
+
+    /// A player on Dire before the horn, playing Axe.
+    pub fn pre_game_dire() -> Self {
+        Self::new()
+            .with_game_rules_state(DotaGameRulesState::PreGame)
+            .with_clock_time(-60)
+            .with_player(PlayerInformation {
+                name: "Dire Player".to_owned(),
+                activity: PlayerActivity::Playing,
+                team_name: Team::Dire,
+                gold: 600,
+                net_worth: Some(600),
+                ..Default::default()
+            })
+            .with_hero(Hero {
+                id: 2,
+                name: Some("npc_dota_hero_axe".to_owned()),
+                level: Some(1),
+                alive: Some(true),
+                ..Default::default()
+            })
+    }
+
+    fn map(&mut self) -> &mut Map {
+        self.state.map.get_or_insert_with(Map::default)
+    }
+
+    /// The player being played, replacing any spectated players.
+    fn player(&mut self) -> &mut PlayerInformation {
+        if !matches!(self.state.players, Some(GamePlayers::Playing(_))) {
+            self.state.players = Some(GamePlayers::Playing(PlayerInformation {
+                activity: PlayerActivity::Playing,
+                ..Default::default()
+            }));
+        }
+
+        match &mut self.state.players {
+            Some(GamePlayers::Playing(player)) => player,
+            _ => unreachable!("players was just set to a playing player"),
+        }
+    }
+
+    /// Set the game clock, in seconds, adding a map if there isn't one.
+    /// The game time is kept 90 seconds ahead, as the clock starts before the horn.
+    pub fn with_clock_time(mut self, seconds: i32) -> Self {
+        let map = self.map();
+        map.clock_time = seconds;
+        map.game_time = u32::try_from(seconds + 90).unwrap_or(0);
+        self
+    }
+
+    /// Set the game rules state, adding a map if there isn't one.
+    pub fn with_game_rules_state(mut self, state: DotaGameRulesState) -> Self {
+        self.map().game_state = state;
+        self
+    }
+
+    /// Set the hero being played.
+    pub fn with_hero(mut self, hero: Hero) -> Self {
+        self.state.heroes = Some(GameHeroes::Playing(hero));
+        self
+    }
+
+    /// Set the player being played.
+    pub fn with_player(mut self, information: PlayerInformation) -> Self {
+        self.state.players = Some(GamePlayers::Playing(information));
+        self
+    }
+
+    /// Set the kills of the player being played, adding a player in a game if there isn't one.
+    pub fn with_player_kills(mut self, kills: u16) -> Self {
+        self.player().kills = kills;
+        self
+    }
+
+    pub fn build(self) -> GameState {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::GameMode;
+
+    #[test]
+    fn test_game_state_builder() {
+        let menu = GameStateBuilder::new().build();
+        assert_eq!(menu, GameState::default());
+        assert_eq!(menu.mode(), GameMode::Menu);
+
+        let gs = GameStateBuilder::new()
+            .with_clock_time(30)
+            .with_player_kills(3)
+            .build();
+        assert_eq!(gs.mode(), GameMode::Playing);
+        assert_eq!(gs.to_summary_json()["clock_time"], 30);
+        assert_eq!(gs.map.as_ref().unwrap().game_time, 120);
+        assert!(matches!(
+            &gs.players,
+            Some(GamePlayers::Playing(p)) if p.kills == 3
+        ));
+    }
+
+    #[test]
+    fn test_game_state_builder_fixtures() {
+        let radiant = GameStateBuilder::mid_game_radiant()
+            .with_player_kills(6)
+            .build();
+        let summary = radiant.to_summary_json();
+
+        assert_eq!(summary["clock_time"], 1200);
+        assert_eq!(summary["hero_name"], "npc_dota_hero_skeleton_king");
+        assert!(radiant.map.as_ref().unwrap().game_state.is_in_game());
+        assert!(matches!(
+            &radiant.players,
+            Some(GamePlayers::Playing(p)) if p.kills == 6 && p.team_name == Team::Radiant
+        ));
+
+        let dire = GameStateBuilder::pre_game_dire().build();
+        assert_eq!(dire.get_hero().map(|h| h.id), Some(2));
+        assert_eq!(dire.map.as_ref().unwrap().clock_time, -60);
+        assert_eq!(dire.map.as_ref().unwrap().game_time, 30);
+    }
+}
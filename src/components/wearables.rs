@@ -18,7 +18,7 @@ pub enum WearablesError {
     EmptyWearablesSlot,
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
 pub struct WearableSlot(u8);
 
 impl fmt::Display for WearableSlot {
@@ -62,7 +62,7 @@ impl Serialize for WearableSlot {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 pub struct Wearable {
     id: Option<u32>,
     style: Option<u32>,
@@ -75,7 +75,7 @@ impl Wearable {
 }
 
 /// Wrapper for Wearable items.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 pub struct Wearables {
     inner: HashMap<WearableSlot, Wearable>,
 }
@@ -131,7 +131,7 @@ impl<'de> Deserialize<'de> for Wearables {
     }
 }
 
-fn deserialize_slot_by_string_split<'de, D, T>(
+pub(crate) fn deserialize_slot_by_string_split<'de, D, T>(
     deserializer: D,
     split_on: Vec<&str>,
 ) -> Result<T, D::Error>
@@ -152,7 +152,7 @@ where
     Err(D::Error::custom(WearablesError::ParseSlotError(s)))
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum GameWearables {
     Spectating(HashMap<Team, HashMap<PlayerID, Wearables>>),
@@ -0,0 +1,276 @@
+//! Configuration files that enable Game State Integration in Dota.
+use std::fmt;
+
+/// The `data` block of a configuration file, selecting the components Dota sends.
+///
+/// Components that are not enabled are missing from every event, so the matching fields of
+/// [`GameState`](crate::components::GameState) will be `None`. Requesting only the required
+/// components reduces the size of each event.
+///
+/// The default block matches the sample configuration file in the README: every component is
+/// enabled except `events`, `minimap` and `couriers`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GsiData {
+    buildings: bool,
+    provider: bool,
+    map: bool,
+    player: bool,
+    hero: bool,
+    abilities: bool,
+    items: bool,
+    draft: bool,
+    wearables: bool,
+    events: bool,
+    minimap: bool,
+    couriers: bool,
+}
+
+impl Default for GsiData {
+    fn default() -> Self {
+        GsiData {
+            buildings: true,
+            provider: true,
+            map: true,
+            player: true,
+            hero: true,
+            abilities: true,
+            items: true,
+            draft: true,
+            wearables: true,
+            events: false,
+            minimap: false,
+            couriers: false,
+        }
+    }
+}
+
+impl GsiData {
+    /// Start building a data block from the default one.
+    pub fn builder() -> GsiDataBuilder {
+        GsiDataBuilder::default()
+    }
+
+    /// Every component, by the name Dota uses for it, and whether it's enabled.
+    pub fn components(&self) -> [(&'static str, bool); 12] {
+        [
+            ("buildings", self.buildings),
+            ("provider", self.provider),
+            ("map", self.map),
+            ("player", self.player),
+            ("hero", self.hero),
+            ("abilities", self.abilities),
+            ("items", self.items),
+            ("draft", self.draft),
+            ("wearables", self.wearables),
+            ("events", self.events),
+            ("minimap", self.minimap),
+            ("couriers", self.couriers),
+        ]
+    }
+
+    /// Whether the component with the given name is enabled.
+    /// Unknown components are never enabled.
+    pub fn is_enabled(&self, component: &str) -> bool {
+        self.components()
+            .iter()
+            .any(|(name, enabled)| *name == component && *enabled)
+    }
+}
+
+/// Formats the block as it's written in a configuration file.
+impl fmt::Display for GsiData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "\"data\"")?;
+        writeln!(f, "{{")?;
+
+        for (name, enabled) in self.components() {
+            writeln!(
+                f,
+                "    {:<16}\"{}\"",
+                format!("\"{}\"", name),
+                if enabled { 1 } else { 0 }
+            )?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+/// A component enabled in a [`GsiData`] block that is missing from an event.
+/// See [`GameState::validate`](crate::components::GameState::validate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MissingComponent {
+    Buildings,
+    Map,
+    Player,
+    Hero,
+    Abilities,
+    Items,
+    Draft,
+    Wearables,
+    Events,
+    Minimap,
+    Couriers,
+}
+
+impl MissingComponent {
+    /// The name Dota uses for this component in the data block.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MissingComponent::Buildings => "buildings",
+            MissingComponent::Map => "map",
+            MissingComponent::Player => "player",
+            MissingComponent::Hero => "hero",
+            MissingComponent::Abilities => "abilities",
+            MissingComponent::Items => "items",
+            MissingComponent::Draft => "draft",
+            MissingComponent::Wearables => "wearables",
+            MissingComponent::Events => "events",
+            MissingComponent::Minimap => "minimap",
+            MissingComponent::Couriers => "couriers",
+        }
+    }
+}
+
+impl fmt::Display for MissingComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Builds a [`GsiData`] block, starting from [`GsiData::default`].
+#[derive(Debug, Clone, Default)]
+pub struct GsiDataBuilder {
+    data: GsiData,
+}
+
+impl GsiDataBuilder {
+    pub fn buildings(mut self, enabled: bool) -> Self {
+        self.data.buildings = enabled;
+        self
+    }
+
+    pub fn provider(mut self, enabled: bool) -> Self {
+        self.data.provider = enabled;
+        self
+    }
+
+    pub fn map(mut self, enabled: bool) -> Self {
+        self.data.map = enabled;
+        self
+    }
+
+    pub fn player(mut self, enabled: bool) -> Self {
+        self.data.player = enabled;
+        self
+    }
+
+    pub fn hero(mut self, enabled: bool) -> Self {
+        self.data.hero = enabled;
+        self
+    }
+
+    pub fn abilities(mut self, enabled: bool) -> Self {
+        self.data.abilities = enabled;
+        self
+    }
+
+    pub fn items(mut self, enabled: bool) -> Self {
+        self.data.items = enabled;
+        self
+    }
+
+    pub fn draft(mut self, enabled: bool) -> Self {
+        self.data.draft = enabled;
+        self
+    }
+
+    pub fn wearables(mut self, enabled: bool) -> Self {
+        self.data.wearables = enabled;
+        self
+    }
+
+    pub fn events(mut self, enabled: bool) -> Self {
+        self.data.events = enabled;
+        self
+    }
+
+    pub fn minimap(mut self, enabled: bool) -> Self {
+        self.data.minimap = enabled;
+        self
+    }
+
+    pub fn couriers(mut self, enabled: bool) -> Self {
+        self.data.couriers = enabled;
+        self
+    }
+
+    /// Disable every component, to enable only the required ones afterwards.
+    pub fn none(mut self) -> Self {
+        self.data = GsiData {
+            buildings: false,
+            provider: false,
+            map: false,
+            player: false,
+            hero: false,
+            abilities: false,
+            items: false,
+            draft: false,
+            wearables: false,
+            events: false,
+            minimap: false,
+            couriers: false,
+        };
+        self
+    }
+
+    pub fn build(self) -> GsiData {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gsi_data_default_matches_sample_cfg() {
+        let data = GsiData::builder().build();
+
+        assert_eq!(data, GsiData::default());
+        assert_eq!(
+            data.components()
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .count(),
+            9
+        );
+        assert!(data.is_enabled("hero"));
+        assert!(!data.is_enabled("events"));
+        assert!(!data.is_enabled("minimap"));
+        assert!(!data.is_enabled("couriers"));
+        assert!(!data.is_enabled("not_a_component"));
+    }
+
+    #[test]
+    fn test_gsi_data_builder() {
+        let data = GsiData::builder()
+            .none()
+            .provider(true)
+            .hero(true)
+            .items(true)
+            .build();
+
+        assert!(data.is_enabled("hero"));
+        assert!(data.is_enabled("items"));
+        assert!(!data.is_enabled("buildings"));
+        assert!(!data.is_enabled("wearables"));
+
+        let rendered = data.to_string();
+        assert!(rendered.starts_with("\"data\"\n{\n"));
+        assert!(rendered.contains("    \"buildings\"     \"0\"\n"));
+        assert!(rendered.contains("    \"provider\"      \"1\"\n"));
+        assert!(rendered.contains("    \"hero\"          \"1\"\n"));
+        assert!(rendered.ends_with('}'));
+    }
+}
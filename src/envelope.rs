@@ -0,0 +1,92 @@
+//! The few blocks of an event read by the server itself, before it's handed to a handler.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::components::{Auth, DotaGameRulesState, Provider};
+
+/// The blocks of an event used to check its provider, route it by token, and derive lifecycle
+/// events. It's parsed once per request and shared between them.
+///
+/// A block that fails to parse is read as missing, so it doesn't hide the others.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct Envelope {
+    #[serde(default, deserialize_with = "ok_or_none")]
+    pub(crate) provider: Option<Provider>,
+    #[serde(default, deserialize_with = "ok_or_none")]
+    pub(crate) auth: Option<Auth>,
+    #[serde(default, deserialize_with = "ok_or_none")]
+    pub(crate) map: Option<EnvelopeMap>,
+    pub(crate) player: Option<Value>,
+}
+
+/// The part of the map block an [`Envelope`] keeps.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct EnvelopeMap {
+    pub(crate) game_state: Option<DotaGameRulesState>,
+}
+
+fn ok_or_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Ok(T::deserialize(Value::deserialize(deserializer)?).ok())
+}
+
+impl Envelope {
+    /// Parse the envelope of a request body.
+    pub(crate) fn parse(body: &[u8]) -> Result<Envelope, serde_json::Error> {
+        serde_json::from_slice(body)
+    }
+
+    /// The token in the auth block, if any.
+    pub(crate) fn auth_token(&self) -> Option<&str> {
+        self.auth.as_ref()?.token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_auth_token() {
+        let token = |body: &[u8]| {
+            Envelope::parse(body)
+                .ok()
+                .and_then(|e| e.auth_token().map(str::to_owned))
+        };
+
+        assert_eq!(
+            token(br#"{"provider": {"name": "Dota 2"}, "auth": {"token": "hello1234"}}"#)
+                .as_deref(),
+            Some("hello1234")
+        );
+        assert_eq!(token(br#"{"auth": {}}"#), None);
+        assert_eq!(token(br#"{"provider": {}}"#), None);
+        assert_eq!(
+            token(br#"{"provider": "Dota 2", "auth": {"token": "a"}}"#).as_deref(),
+            Some("a")
+        );
+        assert_eq!(token(b"not json"), None);
+    }
+
+    #[test]
+    fn test_envelope_parse() {
+        let envelope = Envelope::parse(
+            br#"{"map": {"game_state": "DOTA_GAMERULES_STATE_PRE_GAME", "clock_time": 1}, "player": {"activity": "playing"}, "hero": {}}"#,
+        )
+        .expect("failed to parse envelope");
+
+        assert_eq!(envelope.provider, None);
+        assert_eq!(
+            envelope.map.and_then(|m| m.game_state),
+            Some(DotaGameRulesState::PreGame)
+        );
+        assert_eq!(
+            envelope.player,
+            Some(serde_json::json!({"activity": "playing"}))
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Reusable implementations of [`GameStateHandler`].
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use crate::{GameStateHandler, HandlerError};
+
+#[cfg(feature = "metrics")]
+mod metrics;
+mod registry;
+
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsHandler;
+pub use registry::{DynHandler, HandlerId, HandlerRegistry};
+
+/// Open file backing a [`JsonLinesHandler`] and the bookkeeping required to rotate it.
+#[derive(Debug)]
+struct JsonLinesFile {
+    file: File,
+    written: u64,
+    next_rotation: u32,
+}
+
+/// Append each event as a single compact JSON line to one file.
+///
+/// Recorded sessions can then be replayed line by line. When a maximum size is set with
+/// [`JsonLinesHandler::with_max_bytes`], the file is rotated before it would exceed it:
+/// the full file is renamed to `name.1`, `name.2`, etc., in order, and writing continues on `name`.
+#[derive(Clone, Debug)]
+pub struct JsonLinesHandler {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    inner: Arc<Mutex<JsonLinesFile>>,
+}
+
+impl JsonLinesHandler {
+    /// Open (or create) the file at path for appending JSON lines.
+    pub async fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path).await?;
+        let written = file.metadata().await?.len();
+        let next_rotation = next_rotation_index(&path).await;
+
+        Ok(JsonLinesHandler {
+            path,
+            max_bytes: None,
+            inner: Arc::new(Mutex::new(JsonLinesFile {
+                file,
+                written,
+                next_rotation,
+            })),
+        })
+    }
+
+    /// Rotate the file once writing the next line would make it larger than max_bytes.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a single line, rotating the file beforehand if required.
+    pub async fn write_line(&self, value: &serde_json::Value) -> io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+
+        let mut inner = self.inner.lock().await;
+
+        if let Some(max_bytes) = self.max_bytes {
+            if inner.written > 0 && inner.written + line.len() as u64 > max_bytes {
+                self.rotate(&mut inner).await?;
+            }
+        }
+
+        inner.file.write_all(&line).await?;
+        inner.file.flush().await?;
+        inner.written += line.len() as u64;
+
+        Ok(())
+    }
+
+    async fn rotate(&self, inner: &mut JsonLinesFile) -> io::Result<()> {
+        inner.file.flush().await?;
+
+        let rotated = rotated_path(&self.path, inner.next_rotation);
+        log::debug!("Rotating {:?} to {:?}", self.path, rotated);
+        fs::rename(&self.path, &rotated).await?;
+
+        inner.file = open_append(&self.path).await?;
+        inner.written = 0;
+        inner.next_rotation += 1;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameStateHandler<serde_json::Value> for JsonLinesHandler {
+    async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+        self.write_line(&gs).await?;
+        Ok(())
+    }
+}
+
+/// Forward events to an inner handler only when they differ from the previous event.
+///
+/// Dota sends the game state on a timer even when nothing changed. Events are compared by
+/// hashing them, ignoring `provider.timestamp`, as it changes with every event.
+#[derive(Clone, Debug)]
+pub struct Deduplicate<H> {
+    inner: H,
+    last_hash: Arc<std::sync::Mutex<Option<u64>>>,
+}
+
+impl<H> Deduplicate<H> {
+    pub fn new(inner: H) -> Self {
+        Deduplicate {
+            inner,
+            last_hash: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    /// Record the hash of an event, returning whether it differs from the previous one.
+    fn is_new(&self, hash: u64) -> bool {
+        match self.last_hash.lock() {
+            Ok(mut last_hash) => last_hash.replace(hash) != Some(hash),
+            Err(_) => true,
+        }
+    }
+}
+
+#[async_trait]
+impl<H> GameStateHandler<serde_json::Value> for Deduplicate<H>
+where
+    H: GameStateHandler<serde_json::Value> + Send + Sync,
+{
+    async fn handle(self, mut gs: serde_json::Value) -> Result<(), HandlerError> {
+        let timestamp = gs
+            .get_mut("provider")
+            .and_then(|p| p.as_object_mut())
+            .and_then(|p| p.remove("timestamp"));
+
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_writer(HashWriter(&mut hasher), &gs)?;
+
+        if !self.is_new(hasher.finish()) {
+            log::debug!("Skipping event identical to the previous one");
+            return Ok(());
+        }
+
+        if let (Some(timestamp), Some(provider)) = (
+            timestamp,
+            gs.get_mut("provider").and_then(|p| p.as_object_mut()),
+        ) {
+            provider.insert("timestamp".to_owned(), timestamp);
+        }
+
+        self.inner.handle(gs).await
+    }
+}
+
+/// Error returned by a [`Timeout`] handler when its inner handler took too long.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("handler timed out after {0:?}")]
+pub struct HandlerTimedOut(pub Duration);
+
+/// Cancel the inner handler if handling an event takes longer than a timeout.
+///
+/// This keeps a misbehaving handler, like one stuck on a hung HTTP call, from holding on to
+/// the connection task. Timeouts are returned as a [`HandlerTimedOut`] error, so the server
+/// logs them, and counted across every clone of the handler.
+#[derive(Clone, Debug)]
+pub struct Timeout<H> {
+    inner: H,
+    timeout: Duration,
+    timeouts: Arc<AtomicU64>,
+}
+
+impl<H> Timeout<H> {
+    pub fn new(inner: H, timeout: Duration) -> Self {
+        Timeout {
+            inner,
+            timeout,
+            timeouts: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Number of events the inner handler timed out on.
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl<D, H> GameStateHandler<D> for Timeout<H>
+where
+    D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    H: GameStateHandler<D> + Send + Sync,
+{
+    async fn handle(self, gs: D) -> Result<(), HandlerError> {
+        match tokio::time::timeout(self.timeout, self.inner.handle(gs)).await {
+            Ok(result) => result,
+            Err(_) => {
+                log::warn!("Handler timed out after {:?}", self.timeout);
+                self.timeouts.fetch_add(1, Ordering::Relaxed);
+                Err(Box::new(HandlerTimedOut(self.timeout)))
+            }
+        }
+    }
+}
+
+/// Feed everything written into a hasher, to hash serialized JSON without allocating it.
+struct HashWriter<'a, H>(&'a mut H);
+
+impl<'a, H: Hasher> io::Write for HashWriter<'a, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+fn rotated_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Find the first rotation index not already used by a file, so previous rotations are kept.
+async fn next_rotation_index(path: &Path) -> u32 {
+    let mut index = 1;
+
+    while fs::metadata(rotated_path(path, index)).await.is_ok() {
+        index += 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dota-gsi-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create test directory");
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_handler_appends_lines() {
+        let dir = test_dir("json-lines");
+        let path = dir.join("events.jsonl");
+
+        let handler = JsonLinesHandler::open(&path)
+            .await
+            .expect("failed to open file");
+
+        for i in 0..3 {
+            handler
+                .clone()
+                .handle(serde_json::json!({"provider": {"timestamp": i}}))
+                .await
+                .expect("failed to write line");
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], r#"{"provider":{"timestamp":0}}"#);
+        assert_eq!(lines[2], r#"{"provider":{"timestamp":2}}"#);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_handler_rotates() {
+        let dir = test_dir("json-lines-rotate");
+        let path = dir.join("events.jsonl");
+        let line_length = r#"{"timestamp":0}"#.len() as u64 + 1;
+
+        let handler = JsonLinesHandler::open(&path)
+            .await
+            .expect("failed to open file")
+            .with_max_bytes(line_length * 2);
+
+        for i in 0..5 {
+            handler
+                .clone()
+                .handle(serde_json::json!({ "timestamp": i }))
+                .await
+                .expect("failed to write line");
+        }
+
+        let first = std::fs::read_to_string(dir.join("events.jsonl.1")).unwrap();
+        let second = std::fs::read_to_string(dir.join("events.jsonl.2")).unwrap();
+        let current = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(first, "{\"timestamp\":0}\n{\"timestamp\":1}\n");
+        assert_eq!(second, "{\"timestamp\":2}\n{\"timestamp\":3}\n");
+        assert_eq!(current, "{\"timestamp\":4}\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[derive(Clone, Default)]
+    struct CollectHandler {
+        events: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait]
+    impl GameStateHandler<serde_json::Value> for CollectHandler {
+        async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+            self.events.lock().await.push(gs);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deduplicate_skips_identical_events() {
+        let inner = CollectHandler::default();
+        let handler = Deduplicate::new(inner.clone());
+
+        for (timestamp, clock_time) in [(1, 10), (2, 10), (3, 11), (4, 11), (5, 10)] {
+            handler
+                .clone()
+                .handle(serde_json::json!({
+                    "provider": {"name": "Dota 2", "timestamp": timestamp},
+                    "map": {"clock_time": clock_time}
+                }))
+                .await
+                .expect("failed to handle event");
+        }
+
+        let events = inner.events.lock().await;
+        let timestamps: Vec<u64> = events
+            .iter()
+            .map(|e| e["provider"]["timestamp"].as_u64().unwrap())
+            .collect();
+
+        assert_eq!(timestamps, vec![1, 3, 5]);
+    }
+
+    #[derive(Clone)]
+    struct SleepHandler;
+
+    #[async_trait]
+    impl GameStateHandler<u64> for SleepHandler {
+        async fn handle(self, millis: u64) -> Result<(), HandlerError> {
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_cancels_slow_handler() {
+        let handler = Timeout::new(SleepHandler, Duration::from_millis(50));
+
+        handler
+            .clone()
+            .handle(0)
+            .await
+            .expect("fast handler timed out");
+        assert_eq!(handler.timeouts(), 0);
+
+        let error = handler
+            .clone()
+            .handle(60_000)
+            .await
+            .expect_err("slow handler didn't time out");
+        assert_eq!(
+            error.downcast_ref::<HandlerTimedOut>(),
+            Some(&HandlerTimedOut(Duration::from_millis(50)))
+        );
+        assert_eq!(handler.timeouts(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+//! Expose operational metrics in the Prometheus text format.
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{Encoder, IntCounter, IntGauge, Registry, TextEncoder};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use crate::components::GameState;
+use crate::{
+    normalize_uri, read_request_line, GSIServerError, GameStateHandler, HandlerError, ServerStats,
+    ACCEPT_ERROR_BACKOFF,
+};
+
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+
+/// Time allowed to read a scrape request, unless configured with
+/// [`MetricsHandler::with_read_timeout`].
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The `gsi_parse_errors_total` counter, read from the stats of a server whenever metrics are
+/// gathered.
+#[derive(Clone, Debug)]
+struct ParseErrorsTotal {
+    counter: IntCounter,
+    stats: Arc<ServerStats>,
+}
+
+impl ParseErrorsTotal {
+    const NAME: &'static str = "gsi_parse_errors_total";
+    const HELP: &'static str = "Number of events that failed to parse";
+}
+
+impl Collector for ParseErrorsTotal {
+    fn desc(&self) -> Vec<&Desc> {
+        self.counter.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        // A new counter is collected every time, so concurrent scrapes can't count errors twice.
+        match IntCounter::new(Self::NAME, Self::HELP) {
+            Ok(counter) => {
+                counter.inc_by(self.stats.snapshot().parse_errors);
+                counter.collect()
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Record metrics about the events sent by Dota.
+///
+/// The following metrics are kept:
+/// * `gsi_events_total`: the number of events handled.
+/// * `gsi_parse_errors_total`: the number of events that failed to parse, from the
+///   [`ServerStats`] the handler was created with.
+/// * `gsi_last_provider_timestamp`: the `provider.timestamp` of the last event handled.
+///
+/// Metrics can be scraped from the `/metrics` endpoint started with [`MetricsHandler::serve`].
+#[derive(Clone, Debug)]
+pub struct MetricsHandler {
+    registry: Registry,
+    events_total: IntCounter,
+    last_provider_timestamp: IntGauge,
+    read_timeout: Duration,
+}
+
+impl MetricsHandler {
+    /// Create the metrics of a server, given its stats from [`crate::GSIServer::stats`].
+    pub fn new(stats: Arc<ServerStats>) -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let events_total = IntCounter::new("gsi_events_total", "Number of events handled")?;
+        let parse_errors_total = ParseErrorsTotal {
+            counter: IntCounter::new(ParseErrorsTotal::NAME, ParseErrorsTotal::HELP)?,
+            stats,
+        };
+        let last_provider_timestamp = IntGauge::new(
+            "gsi_last_provider_timestamp",
+            "Provider timestamp of the last event handled",
+        )?;
+
+        registry.register(Box::new(events_total.clone()))?;
+        registry.register(Box::new(parse_errors_total))?;
+        registry.register(Box::new(last_provider_timestamp.clone()))?;
+
+        Ok(MetricsHandler {
+            registry,
+            events_total,
+            last_provider_timestamp,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+        })
+    }
+
+    /// Set the time allowed to read a scrape request, before the connection is dropped.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Encode all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        let encoder = TextEncoder::new();
+
+        if let Err(e) = encoder.encode(&self.registry.gather(), &mut buf) {
+            log::error!("failed to encode metrics: {}", e);
+        }
+
+        String::from_utf8(buf).unwrap_or_default()
+    }
+
+    /// Serve metrics on the `/metrics` path of the given URI, like `127.0.0.1:9090` or
+    /// `http://127.0.0.1:9090/`. This should be a different URI than the one Dota sends events to.
+    /// Only binding fails: errors accepting a scrape are logged, and serving continues.
+    pub async fn serve(self, uri: &str) -> Result<(), GSIServerError> {
+        let listener = TcpListener::bind(normalize_uri(uri)).await?;
+        log::info!("Serving metrics on: {:?}", listener.local_addr());
+
+        loop {
+            let socket = match listener.accept().await {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    log::error!("failed to accept metrics scrape: {}", e);
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+            let this = self.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = this.respond(socket).await {
+                    log::error!("failed to serve metrics: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn respond<S>(&self, mut socket: S) -> Result<(), GSIServerError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let request = read_request_line(&mut socket, Some(self.read_timeout)).await?;
+
+        if request.path == "/metrics" {
+            let body = self.render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await?;
+        } else {
+            socket.write_all(NOT_FOUND.as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameStateHandler<GameState> for MetricsHandler {
+    async fn handle(self, gs: GameState) -> Result<(), HandlerError> {
+        self.events_total.inc();
+        self.last_provider_timestamp
+            .set(gs.get_provider().timestamp() as i64);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    const GAME_STATE: &str = r#"{
+        "provider": {
+            "name": "Dota 2",
+            "appid": 570,
+            "version": 47,
+            "timestamp": 1688514013
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_metrics_handler() {
+        let stats = Arc::new(ServerStats::default());
+        let handler = MetricsHandler::new(stats.clone()).expect("failed to create metrics");
+        let gs: GameState = serde_json::from_str(GAME_STATE).unwrap();
+
+        handler.clone().handle(gs.clone()).await.unwrap();
+        handler.clone().handle(gs).await.unwrap();
+        stats.record_parse_error();
+
+        let rendered = handler.render();
+        assert!(rendered.contains("gsi_events_total 2"));
+        assert!(rendered.contains("gsi_parse_errors_total 1"));
+        assert!(rendered.contains("gsi_last_provider_timestamp 1688514013"));
+
+        stats.record_parse_error();
+        assert!(handler.render().contains("gsi_parse_errors_total 2"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_handler_serve() {
+        let handler = MetricsHandler::new(Arc::new(ServerStats::default()))
+            .expect("failed to create metrics");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let server_handler = handler.clone();
+        tokio::spawn(async move {
+            for _ in 0..2 {
+                let (socket, _) = listener.accept().await.unwrap();
+                server_handler.respond(socket).await.unwrap();
+            }
+        });
+
+        for (path, expected) in [
+            ("/metrics", "HTTP/1.1 200 OK"),
+            ("/", "HTTP/1.1 404 Not Found"),
+        ] {
+            let mut stream = tokio::net::TcpStream::connect(local_addr).await.unwrap();
+            let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with(expected));
+
+            if path == "/metrics" {
+                assert!(response.contains("gsi_events_total 0"));
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Handlers added and removed while a server runs.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use crate::{GameStateHandler, HandlerError};
+
+/// An object-safe handler of events parsed as JSON, as registered in a [`HandlerRegistry`].
+///
+/// Implemented for every `Clone` [`GameStateHandler`] of [`serde_json::Value`], by handling
+/// each event with a clone of the handler.
+#[async_trait]
+pub trait DynHandler: Send + Sync {
+    async fn handle(&self, gs: serde_json::Value) -> Result<(), HandlerError>;
+}
+
+#[async_trait]
+impl<H> DynHandler for H
+where
+    H: GameStateHandler<serde_json::Value> + Clone + Send + Sync,
+{
+    async fn handle(&self, gs: serde_json::Value) -> Result<(), HandlerError> {
+        GameStateHandler::handle(self.clone(), gs).await
+    }
+}
+
+/// Identifies a handler registered in a [`HandlerRegistry`], to unregister it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(u64);
+
+#[derive(Default)]
+struct Registry {
+    next_id: AtomicU64,
+    handlers: RwLock<Vec<(HandlerId, Arc<dyn DynHandler>)>>,
+}
+
+/// Handlers that can be registered and unregistered at runtime.
+///
+/// A registry is itself a [`GameStateHandler`] of [`serde_json::Value`], so it runs like any
+/// other handler, with [`GSIServer::run_with_handler`](crate::GSIServer::run_with_handler) or
+/// [`ServerBuilder::register`](crate::ServerBuilder::register).
+///
+/// Clones share the same handlers, so a clone kept by the application can change the handlers
+/// of a running server. Each event is handled by the handlers registered when it's received,
+/// sequentially, in the order they were registered.
+#[derive(Clone, Default)]
+pub struct HandlerRegistry {
+    inner: Arc<Registry>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler, returning the ID to unregister it with.
+    pub fn register<H>(&self, handler: H) -> HandlerId
+    where
+        H: DynHandler + 'static,
+    {
+        let id = HandlerId(self.inner.next_id.fetch_add(1, Ordering::Relaxed));
+        self.inner
+            .handlers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, Arc::new(handler)));
+        id
+    }
+
+    /// Unregister a handler. Returns false if it wasn't registered.
+    /// Events already being handled are still handled by it.
+    pub fn unregister(&self, id: HandlerId) -> bool {
+        let mut handlers = self
+            .inner
+            .handlers
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        let registered = handlers.len();
+        handlers.retain(|(handler_id, _)| *handler_id != id);
+
+        handlers.len() != registered
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers().is_empty()
+    }
+
+    /// The currently registered handlers, so they can be called without holding the lock.
+    fn handlers(&self) -> Vec<Arc<dyn DynHandler>> {
+        self.inner
+            .handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, handler)| handler.clone())
+            .collect()
+    }
+}
+
+/// Every registered handler handles the event, even after one fails. The first error is
+/// returned, and any later ones are logged.
+#[async_trait]
+impl GameStateHandler<serde_json::Value> for HandlerRegistry {
+    async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+        let mut result = Ok(());
+
+        for handler in self.handlers() {
+            if let Err(e) = handler.handle(gs.clone()).await {
+                match result {
+                    Ok(()) => result = Err(e),
+                    Err(_) => log::error!("handler failed to handle game state: {}", e),
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl std::fmt::Debug for HandlerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("HandlerRegistry")
+            .field("handlers", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::sync::Mutex;
+
+    #[derive(Clone)]
+    struct Collect {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl GameStateHandler<serde_json::Value> for Collect {
+        async fn handle(self, _gs: serde_json::Value) -> Result<(), HandlerError> {
+            self.calls.lock().await.push(self.name);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_registry() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let collect = |name| Collect {
+            name,
+            calls: calls.clone(),
+        };
+        let registry = HandlerRegistry::new();
+        assert!(registry.is_empty());
+
+        let first = registry.register(collect("first"));
+        let second = registry.clone().register(collect("second"));
+        assert_ne!(first, second);
+        assert_eq!(registry.len(), 2);
+
+        let event = serde_json::json!({});
+        registry.clone().handle(event.clone()).await.unwrap();
+        assert!(registry.unregister(first));
+        assert!(!registry.unregister(first));
+        registry.clone().handle(event).await.unwrap();
+
+        assert_eq!(*calls.lock().await, vec!["first", "second", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_handler_registry_keeps_handling_after_error() {
+        #[derive(Clone)]
+        struct Fail;
+
+        #[async_trait]
+        impl GameStateHandler<serde_json::Value> for Fail {
+            async fn handle(self, _gs: serde_json::Value) -> Result<(), HandlerError> {
+                Err("failed".into())
+            }
+        }
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let registry = HandlerRegistry::new();
+        registry.register(Fail);
+        registry.register(Collect {
+            name: "after",
+            calls: calls.clone(),
+        });
+
+        let result = registry.handle(serde_json::json!({})).await;
+
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+        assert_eq!(*calls.lock().await, vec!["after"]);
+    }
+}
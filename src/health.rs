@@ -0,0 +1,147 @@
+//! A liveness endpoint served alongside a [`GSIServer`](crate::GSIServer).
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use crate::stats::ServerStats;
+use crate::{read_request_line, GSIServerError, ACCEPT_ERROR_BACKOFF};
+
+const METHOD_NOT_ALLOWED: &str =
+    "HTTP/1.1 405 Method Not Allowed\r\nAllow: GET\r\nContent-Length: 0\r\n\r\n";
+
+/// The body of a health check response.
+/// `seconds_since_last_event` is null until the first event is processed.
+fn to_json(stats: &ServerStats) -> serde_json::Value {
+    let snapshot = stats.snapshot();
+
+    serde_json::json!({
+        "events_processed": snapshot.events,
+        "seconds_since_last_event": snapshot.since_last_event().map(|d| d.as_secs_f64()),
+    })
+}
+
+/// Respond to a single health check: any GET is answered with the stats as JSON.
+/// Like events, reading the request fails once read_timeout is exceeded.
+async fn respond<S>(
+    stats: &ServerStats,
+    mut socket: S,
+    read_timeout: Option<Duration>,
+) -> Result<(), GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = read_request_line(&mut socket, read_timeout).await?;
+
+    if request.method == "GET" {
+        let body = to_json(stats).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await?;
+    } else {
+        socket.write_all(METHOD_NOT_ALLOWED.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Answer health checks on listener.
+/// Stops, closing the listener, once the sender of shutdown is dropped with the server owning it.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    stats: Arc<ServerStats>,
+    read_timeout: Option<Duration>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => accepted,
+        };
+
+        let socket = match accepted {
+            Ok((socket, _)) => socket,
+            Err(e) => {
+                log::error!("failed to accept health check: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let stats = stats.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(&stats, socket, read_timeout).await {
+                log::error!("failed to respond to health check: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    async fn request(stats: &ServerStats, request: &[u8]) -> String {
+        let (mut client, stream) = tokio::io::duplex(1024);
+        client.write_all(request).await.unwrap();
+
+        respond(stats, stream, None)
+            .await
+            .expect("failed to respond");
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_health_respond() {
+        let stats = ServerStats::default();
+
+        let response = request(&stats, b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"{"events_processed":0,"seconds_since_last_event":null}"#));
+
+        stats.record_event(10);
+        stats.record_event(10);
+
+        let response = request(&stats, b"GET /health HTTP/1.1\r\n\r\n").await;
+        let body: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["events_processed"], 2);
+        assert!(body["seconds_since_last_event"].as_f64().unwrap() < 1.0);
+
+        let response = request(&stats, b"POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_health_serve_stops_on_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+        let (shutdown, signal) = watch::channel(());
+
+        let serving = tokio::spawn(serve(
+            listener,
+            Arc::new(ServerStats::default()),
+            None,
+            signal,
+        ));
+        drop(shutdown);
+
+        tokio::time::timeout(Duration::from_secs(1), serving)
+            .await
+            .expect("health checks kept being served after shutdown")
+            .unwrap();
+        assert!(tokio::net::TcpStream::connect(local_addr).await.is_err());
+    }
+}
@@ -41,31 +41,96 @@
 //!
 //! [configuration file]: https://developer.valvesoftware.com/wiki/Counter-Strike:_Global_Offensive_Game_State_Integration
 //! [launch option]: https://help.steampowered.com/en/faqs/view/7d01-d2dd-d75e-2955
+use std::collections::HashMap;
+use std::fmt;
 use std::future::Future;
 use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use serde::de::DeserializeOwned;
 use thiserror::Error;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, watch};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::task;
+use tokio::time::Instant;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+#[cfg(feature = "tracing")]
+use tracing as log;
+
+use crate::envelope::Envelope;
+use crate::lifecycle::Lifecycle;
 
 pub mod components;
+pub mod config;
+mod envelope;
+pub mod handlers;
+mod health;
+mod lifecycle;
+pub mod prelude;
+pub mod replay;
+mod spans;
+mod stats;
+#[cfg(feature = "tls")]
+mod tls;
+
+pub use lifecycle::LifecycleEvent;
+pub use stats::{ServerStats, StatsSnapshot};
 
 /// The payload sent by Dota is usually between 50-60kb.
-/// We initialize a buffer to read the request with this initial capacity.
+/// We initialize a buffer to read the request with this initial capacity, unless configured
+/// with [`GSIServer::with_initial_buffer_capacity`].
 /// The code then looks at the Content-Length header to reserve the required capacity.
-const INITIAL_REQUEST_BUFFER_CAPACITY_BYTES: usize = 1024;
+pub const INITIAL_REQUEST_BUFFER_CAPACITY_BYTES: usize = 1024;
+
+/// The POST request sent by Dota includes 7 headers, and a reverse proxy may add more.
+/// We parse them to find the Content-Length and Content-Encoding.
+const EXPECTED_NUMBER_OF_HEADERS: usize = 16;
+
+/// Requests declaring a larger body than this are rejected before reserving any memory.
+/// It sits well above the size of the payloads sent by Dota.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Maximum number of request buffers kept for reuse by a server.
+const MAX_POOLED_BUFFERS: usize = 16;
+
+/// Time to wait before accepting again after failing to accept a connection, like when the
+/// process runs out of file descriptors, so the error isn't retried in a busy loop.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Maximum number of parsed events waiting to be consumed from a stream returned by
+/// [`GSIServer::into_stream`], before the server stops accepting connections.
+const STREAM_BUFFER_SIZE: usize = 16;
+
+/// Maximum length of the request line and headers of any request, whether an event or a request
+/// without a body, like a health check or a metrics scrape.
+/// Longer requests are rejected with [`GSIServerError::IncompleteHeaders`].
+const MAX_REQUEST_HEAD_BYTES: usize = 8 * 1024;
 
-/// The POST request sent by Dota includes a number of headers.
-/// We parse them to find the Content-Length.
-const EXPECTED_NUMBER_OF_HEADERS: usize = 7;
+/// Bodies attached to errors are truncated to this length when displayed, to keep logs readable.
+const MAX_DISPLAYED_BODY_BYTES: usize = 4 * 1024;
 
 /// The response expected by every GameState Integration request.
 /// Failure to deliver this response would cause the request to be retried infinitely.
-const OK: &str = "HTTP/1.1 200 OK\ncontent-type: text/html\n";
+/// Dota only requires a 2xx status, so the response is kept minimal but spec-compliant.
+pub const OK: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 0\r\n\r\n";
+
+/// The response to requests with any method other than POST, which Dota never sends.
+const METHOD_NOT_ALLOWED: &str =
+    "HTTP/1.1 405 Method Not Allowed\r\nAllow: POST\r\nContent-Length: 0\r\n\r\n";
 
 #[derive(Error, Debug)]
 pub enum GSIServerError {
@@ -77,298 +142,3298 @@ pub enum GSIServerError {
     SocketClosed,
     #[error("failed to complete the assigned GSI task")]
     TaskError(#[from] task::JoinError),
-    #[error("failed to parse game state integration from JSON")]
-    ParseJSONError(#[from] serde_json::Error),
     #[error("failed to parse Content-Length Header sent by Dota")]
     ParseContentLengthError(String),
     #[error("failed to parse Request sent by Dota")]
     ParseRequestError(#[from] httparse::Error),
+    #[error("timed out after {0:?} reading request sent by Dota")]
+    ReadTimeout(Duration),
+    #[error("request body of {declared} bytes exceeds the limit of {limit} bytes")]
+    BodyTooLarge { declared: usize, limit: usize },
+    #[error("invalid URI to listen on: `{0}`")]
+    InvalidUri(String),
+    #[error("failed to decode gzip encoded request body")]
+    DecodeBodyError(#[source] io::Error),
+    #[error("handler failed to handle game state: {0}")]
+    HandlerError(#[source] HandlerError),
+    #[error("rejected event not sent by Dota 2: {0}")]
+    UnexpectedProvider(String),
+    #[error("rejected {0} request, Dota only sends POST requests")]
+    UnexpectedMethod(String),
+    #[error("TLS error: {0}")]
+    TlsError(#[source] io::Error),
+    #[error(
+        "failed to parse game state integration from JSON body: {source}: {}",
+        display_body(.body)
+    )]
+    ParseBodyError {
+        body: Bytes,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Variants carrying only comparable data, like [`GSIServerError::SocketClosed`] or
+/// [`GSIServerError::ParseContentLengthError`], compare structurally. So do variants wrapping an
+/// [`httparse::Error`]. Variants wrapping other errors compare by kind instead:
+/// * [`io::Error`]s compare by their [`io::ErrorKind`], including TLS errors.
+/// * [`serde_json::Error`]s compare by their [`serde_json::error::Category`].
+/// * [`task::JoinError`]s compare by whether the task was cancelled or panicked.
+/// * [`HandlerError`]s compare by their message.
+impl PartialEq for GSIServerError {
+    fn eq(&self, other: &Self) -> bool {
+        use GSIServerError::*;
+
+        match (self, other) {
+            (IncompleteHeaders, IncompleteHeaders) => true,
+            (SocketError(a), SocketError(b)) => a.kind() == b.kind(),
+            (SocketClosed, SocketClosed) => true,
+            (TaskError(a), TaskError(b)) => {
+                a.is_cancelled() == b.is_cancelled() && a.is_panic() == b.is_panic()
+            }
+            (ParseContentLengthError(a), ParseContentLengthError(b)) => a == b,
+            (ParseRequestError(a), ParseRequestError(b)) => a == b,
+            (ReadTimeout(a), ReadTimeout(b)) => a == b,
+            (
+                BodyTooLarge {
+                    declared: a_declared,
+                    limit: a_limit,
+                },
+                BodyTooLarge {
+                    declared: b_declared,
+                    limit: b_limit,
+                },
+            ) => a_declared == b_declared && a_limit == b_limit,
+            (InvalidUri(a), InvalidUri(b)) => a == b,
+            (DecodeBodyError(a), DecodeBodyError(b)) => a.kind() == b.kind(),
+            (HandlerError(a), HandlerError(b)) => a.to_string() == b.to_string(),
+            (UnexpectedProvider(a), UnexpectedProvider(b)) => a == b,
+            (UnexpectedMethod(a), UnexpectedMethod(b)) => a == b,
+            (TlsError(a), TlsError(b)) => a.kind() == b.kind(),
+            (
+                ParseBodyError {
+                    body: a_body,
+                    source: a_source,
+                },
+                ParseBodyError {
+                    body: b_body,
+                    source: b_source,
+                },
+            ) => a_body == b_body && a_source.classify() == b_source.classify(),
+            _ => false,
+        }
+    }
+}
+
+/// Display a request body, truncated to [`MAX_DISPLAYED_BODY_BYTES`].
+fn display_body(body: &Bytes) -> String {
+    if body.len() <= MAX_DISPLAYED_BODY_BYTES {
+        return String::from_utf8_lossy(body).into_owned();
+    }
+
+    format!(
+        "{}... ({} more bytes)",
+        String::from_utf8_lossy(&body[..MAX_DISPLAYED_BODY_BYTES]),
+        body.len() - MAX_DISPLAYED_BODY_BYTES
+    )
 }
 
+/// An error returned by a handler that failed to handle Game State data.
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
 /// Trait implemented by handlers of Game State data.
+///
+/// Errors returned by handlers are logged by the server and sent to the sink set with
+/// [`GSIServer::on_error`], wrapped in [`GSIServerError::HandlerError`].
 #[async_trait]
 pub trait GameStateHandler<D>
 where
     D: DeserializeOwned + std::fmt::Debug + Send + 'static,
 {
-    async fn handle(self, gs: D);
+    async fn handle(self, gs: D) -> Result<(), HandlerError>;
 }
 
-/// A server that handles GameState Integration requests from Dota.
-/// The URI used in the configuration file must be the same URI used when creating a new [`GSIServer`].
-pub struct GSIServer {
-    uri: String,
+/// Trait implemented by handlers of Game State data that also need the request that sent it,
+/// like headers added by a proxy, or the address of the peer, to tell game instances apart.
+///
+/// Run with [`GSIServer::run_with_headers_handler`]. Errors are reported like those of a
+/// [`GameStateHandler`].
+#[async_trait]
+pub trait GameStateHandlerWithHeaders<D>
+where
+    D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+{
+    /// Handle an event, given the headers and peer of the request that sent it.
+    async fn handle(self, request: RequestContext, gs: D) -> Result<(), HandlerError>;
 }
 
-impl Default for GSIServer {
-    fn default() -> Self {
-        GSIServer {
-            uri: "127.0.0.1:3000".to_owned(),
-        }
-    }
+/// The request an event was sent in, as passed to a [`GameStateHandlerWithHeaders`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    headers: Headers,
+    peer: Peer,
 }
 
-impl GSIServer {
-    /// Create a new GSIServer with given URI.
-    pub fn new(uri: &str) -> Self {
-        GSIServer {
-            uri: uri.to_owned(),
-        }
+impl RequestContext {
+    /// The HTTP headers of the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
     }
 
-    /// Run the Game State Integration server.
-    /// A handler function is taken to process the data sent by Dota 2.
-    pub async fn run<D, U>(
-        self,
-        handler: impl Fn(D) -> U + Sync + Send + Copy + 'static,
-    ) -> Result<(), GSIServerError>
-    where
-        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
-        U: Future + Send + Sync + 'static,
-        U::Output: Send,
-    {
-        let listener = TcpListener::bind(self.uri).await?;
-        log::info!("Listening on: {:?}", listener.local_addr());
+    /// The address of the peer that sent the request.
+    pub fn peer(&self) -> &Peer {
+        &self.peer
+    }
+}
 
-        loop {
-            let (socket, addr) = listener.accept().await?;
-            log::info!("Accepted: {}", addr);
+/// The HTTP headers of a request, in the order they were sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
 
-            tokio::spawn(async move {
-                log::debug!("Task spawned");
+impl Headers {
+    /// Collect the headers already parsed by the server while reading a request.
+    /// Values that aren't valid UTF-8 are converted lossily.
+    fn from_parsed(headers: &[httparse::Header]) -> Headers {
+        Headers(
+            headers
+                .iter()
+                .take_while(|h| !h.name.is_empty())
+                .map(|h| {
+                    (
+                        h.name.to_owned(),
+                        String::from_utf8_lossy(h.value).into_owned(),
+                    )
+                })
+                .collect(),
+        )
+    }
 
-                match process(socket).await {
-                    Err(e) => {
-                        log::error!("{}", e);
-                        return Err(e);
-                    }
-                    Ok(buf) => match serde_json::from_slice(&buf) {
-                        Err(e) => {
-                            log::debug!("{:?}", buf);
-                            log::error!("Failed to parse JSON body: {}", e);
-                            return Err(GSIServerError::from(e));
-                        }
-                        Ok(parsed) => {
-                            handler(parsed).await;
-                        }
-                    },
-                };
+    /// The value of the first header with the given name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
 
-                Ok(())
-            });
-        }
+    /// Iterate over every header as a name and value pair.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(n, v)| (n.as_str(), v.as_str()))
     }
 
-    /// Run the Game State Integration server.
-    /// A handler function is taken to process the data sent by Dota 2.
-    pub async fn run_with_handler<D>(
-        self,
-        handler: impl GameStateHandler<D> + Send + Sync + Clone + 'static,
-    ) -> Result<(), GSIServerError>
-    where
-        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
-    {
-        let listener = TcpListener::bind(self.uri).await?;
-        log::info!("Listening on: {:?}", listener.local_addr());
+    /// The number of headers, counting repeated names once per occurrence.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
 
-        loop {
-            let (socket, addr) = listener.accept().await?;
-            log::info!("Accepted: {}", addr);
-            // Need to clone as handler will be moved by spawn.
-            let this_handler = handler.clone();
+    /// Whether the request was sent without any headers.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
 
-            tokio::spawn(async move {
-                log::debug!("Task spawned");
+/// An optional callback, shared by every connection task.
+struct Sink<T>(Option<Arc<dyn Fn(T) + Send + Sync>>);
 
-                match process(socket).await {
-                    Err(e) => {
-                        log::error!("{}", e);
-                        return Err(e);
-                    }
-                    Ok(buf) => match serde_json::from_slice(&buf) {
-                        Err(e) => {
-                            log::error!("Failed to parse JSON body: {}", e);
-                            return Err(GSIServerError::from(e));
-                        }
-                        Ok(parsed) => {
-                            this_handler.handle(parsed).await;
-                        }
-                    },
-                };
+impl<T> Sink<T> {
+    fn send(&self, value: T) {
+        if let Some(sink) = &self.0 {
+            sink(value);
+        }
+    }
+}
 
-                Ok(())
-            });
+impl<T> Clone for Sink<T> {
+    fn clone(&self) -> Self {
+        Sink(self.0.clone())
+    }
+}
+
+impl<T> Default for Sink<T> {
+    fn default() -> Self {
+        Sink(None)
+    }
+}
+
+impl<T> std::fmt::Debug for Sink<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Sink(Some(..))"),
+            None => write!(f, "Sink(None)"),
         }
     }
 }
 
-/// Process a TcpStream.
-/// Ensures the stream's contents can be parsed and returns an appropiate response to Dota.
-pub async fn process(mut socket: TcpStream) -> Result<BytesMut, GSIServerError> {
-    if let Err(e) = socket.readable().await {
-        log::error!("socket is not readable");
-        return Err(GSIServerError::from(e));
-    };
+/// Request buffers recycled across connections.
+///
+/// Buffers are returned to the pool once a request has been read. A returned buffer still shares
+/// its allocation with the body handed to handlers, but once the body is dropped, reserving
+/// capacity for the next request reclaims the allocation instead of allocating a new one.
+#[derive(Clone, Debug)]
+struct BufferPool {
+    buffers: Arc<std::sync::Mutex<Vec<BytesMut>>>,
+    initial_capacity: usize,
+}
 
-    let mut buf = BytesMut::with_capacity(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
-    let request_length: usize;
-    let content_length: usize;
+impl Default for BufferPool {
+    fn default() -> Self {
+        BufferPool::new(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES)
+    }
+}
 
-    loop {
-        match socket.read_buf(&mut buf).await {
-            Ok(n) => n,
-            Err(e) => {
-                log::error!("failed to read request from socket: {}", e);
-                return Err(GSIServerError::from(e));
-            }
+impl BufferPool {
+    /// Create an empty pool, allocating new buffers with the given capacity.
+    fn new(initial_capacity: usize) -> Self {
+        BufferPool {
+            buffers: Arc::default(),
+            initial_capacity,
+        }
+    }
+
+    fn take(&self) -> BytesMut {
+        let recycled = match self.buffers.lock() {
+            Ok(mut buffers) => buffers.pop(),
+            Err(_) => None,
         };
 
-        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
-        let mut r = httparse::Request::new(&mut headers);
+        recycled.unwrap_or_else(|| BytesMut::with_capacity(self.initial_capacity))
+    }
 
-        request_length = match r.parse(&buf) {
-            Ok(httparse::Status::Complete(size)) => size,
-            Ok(httparse::Status::Partial) => {
-                log::debug!("partial request parsed, need to read more");
-                continue;
-            }
-            Err(e) => {
-                log::error!("failed to parse request: {}", e);
-                return Err(GSIServerError::from(e));
+    fn give(&self, mut buf: BytesMut) {
+        buf.clear();
+
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < MAX_POOLED_BUFFERS {
+                buffers.push(buf);
             }
-        };
-        content_length = get_content_length_from_headers(&headers)?;
-        break;
+        }
     }
+}
 
-    if buf.len() <= request_length + content_length {
-        buf.reserve(request_length + content_length);
-        match socket.read_buf(&mut buf).await {
-            Ok(n) => n,
-            Err(e) => {
-                log::error!("failed to read body from socket: {}", e);
-                return Err(GSIServerError::from(e));
+/// Drops events received sooner than an interval after the last event that was handled.
+#[derive(Debug)]
+struct Throttle {
+    interval: Duration,
+    last_forwarded: std::sync::Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    fn new(interval: Duration) -> Self {
+        Throttle {
+            interval,
+            last_forwarded: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Whether an event received now should be handled, recording it if so.
+    fn allow(&self) -> bool {
+        let mut last_forwarded = self
+            .last_forwarded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        match *last_forwarded {
+            Some(last) if now.duration_since(last) < self.interval => false,
+            _ => {
+                *last_forwarded = Some(now);
+                true
             }
+        }
+    }
+}
+
+/// Detects when Dota stops sending events.
+///
+/// Dota keeps sending the game state at least every `heartbeat` seconds, as set in the
+/// configuration file, even when nothing changes. Once no event arrives within the timeout,
+/// the callback is called, and it won't be called again until another event arrives.
+#[derive(Debug)]
+struct Heartbeat {
+    timeout: Duration,
+    last_event: std::sync::Mutex<Instant>,
+    timed_out: AtomicBool,
+    on_timeout: Sink<()>,
+}
+
+impl Heartbeat {
+    fn new(timeout: Duration, on_timeout: Sink<()>) -> Self {
+        Heartbeat {
+            timeout,
+            last_event: std::sync::Mutex::new(Instant::now()),
+            timed_out: AtomicBool::new(false),
+            on_timeout,
+        }
+    }
+
+    /// Record that an event was just received.
+    fn beat(&self) {
+        if let Ok(mut last_event) = self.last_event.lock() {
+            *last_event = Instant::now();
+        }
+        self.timed_out.store(false, Ordering::SeqCst);
+    }
+
+    /// Call the callback if the timeout elapsed since the last event, once per timeout.
+    fn check(&self) {
+        let elapsed = match self.last_event.lock() {
+            Ok(last_event) => last_event.elapsed(),
+            Err(_) => return,
         };
+
+        if elapsed >= self.timeout && !self.timed_out.swap(true, Ordering::SeqCst) {
+            log::warn!("No events received in the last {:?}", elapsed);
+            self.on_timeout.send(());
+        }
     }
 
-    if let Err(e) = socket.write_all(OK.as_bytes()).await {
-        log::error!("failed to write to socket: {}", e);
-        return Err(GSIServerError::from(e));
-    };
+    /// Spawn a task checking for timeouts, starting from now.
+    /// The task stops once the heartbeat is dropped, together with the server that owns it.
+    fn watch(self: &Arc<Self>) {
+        self.beat();
 
-    Ok(buf.split_off(request_length))
-}
+        let heartbeat = Arc::downgrade(self);
+        let period = (self.timeout / 4).max(Duration::from_millis(1));
 
-/// Extract Content-Length value from a list of HTTP headers.
-pub fn get_content_length_from_headers(
-    headers: &[httparse::Header],
-) -> Result<usize, GSIServerError> {
-    match headers
-        .iter()
-        .filter(|h| h.name == "Content-Length")
-        .map(|h| h.value)
-        .next()
-    {
-        Some(value) => {
-            let str_length = match std::str::from_utf8(value) {
-                Ok(s) => s,
-                Err(e) => {
-                    return Err(GSIServerError::ParseContentLengthError(format!(
-                        "failed to parse bytes as str: {}",
-                        e
-                    )))
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+
+            loop {
+                interval.tick().await;
+
+                match heartbeat.upgrade() {
+                    Some(heartbeat) => heartbeat.check(),
+                    None => break,
                 }
-            };
-            match str_length.parse::<usize>() {
-                Ok(n) => Ok(n),
-                Err(e) => Err(GSIServerError::ParseContentLengthError(format!(
-                    "failed to parse str into usize: {}",
-                    e
-                ))),
             }
+        });
+    }
+}
+
+/// Options applied to every connection accepted by a [`GSIServer`].
+#[derive(Clone, Debug)]
+struct ConnectionConfig {
+    response: Arc<str>,
+    read_timeout: Option<Duration>,
+    max_body_bytes: usize,
+    errors: Sink<GSIServerError>,
+    lenient: bool,
+    raw: Sink<serde_json::Value>,
+    buffers: BufferPool,
+    heartbeat: Option<Arc<Heartbeat>>,
+    throttle: Option<Arc<Throttle>>,
+    lifecycle: Option<Arc<Lifecycle>>,
+    stats: Arc<ServerStats>,
+    require_dota_provider: bool,
+    #[cfg(feature = "tls")]
+    tls: Option<tls::Tls>,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        ConnectionConfig {
+            response: Arc::from(OK),
+            read_timeout: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            errors: Sink::default(),
+            lenient: false,
+            raw: Sink::default(),
+            buffers: BufferPool::default(),
+            heartbeat: None,
+            throttle: None,
+            lifecycle: None,
+            stats: Arc::default(),
+            require_dota_provider: false,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
-        None => Err(GSIServerError::ParseContentLengthError(
-            "Content-Length header not found".to_string(),
-        )),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl ConnectionConfig {
+    /// Process a stream with these options, completing the TLS handshake first if configured.
+    /// When the read timeout is exceeded, the socket is dropped, closing the connection.
+    async fn process<S>(&self, mut socket: S) -> Result<Request, GSIServerError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut buf = self.buffers.take();
+        let processing = async {
+            #[cfg(feature = "tls")]
+            if let Some(tls) = &self.tls {
+                let mut stream = tls.accept(socket).await?;
+                return read_request_parts(
+                    &mut stream,
+                    &mut buf,
+                    self.response.as_bytes(),
+                    self.max_body_bytes,
+                )
+                .await;
+            }
 
-    const TEST_URI: &'static str = "127.0.0.1:0";
+            read_request_parts(
+                &mut socket,
+                &mut buf,
+                self.response.as_bytes(),
+                self.max_body_bytes,
+            )
+            .await
+        };
 
-    #[test]
-    fn test_get_content_length_from_headers() {
-        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
-        let mut r = httparse::Request::new(&mut headers);
-        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: 54943\r\n\r\n";
-        r.parse(request_bytes)
-            .expect("parsing the request should never fail");
+        let result = match self.read_timeout {
+            None => processing.await,
+            Some(timeout) => match tokio::time::timeout(timeout, processing).await {
+                Ok(result) => result,
+                Err(_) => Err(GSIServerError::ReadTimeout(timeout)),
+            },
+        };
 
-        let expected = 54943 as usize;
-        let content_length =
-            get_content_length_from_headers(&r.headers).expect("failed to get Content-Length");
+        self.buffers.give(buf);
 
-        assert_eq!(content_length, expected);
-    }
+        if let (Ok(_), Some(heartbeat)) = (&result, &self.heartbeat) {
+            heartbeat.beat();
+        }
 
-    #[test]
-    fn test_get_content_length_from_headers_not_found() {
-        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
-        let mut r = httparse::Request::new(&mut headers);
-        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\n\r\n";
-        r.parse(request_bytes)
-            .expect("parsing the request should never fail");
+        if let Ok(request) = &result {
+            self.stats.record_event(request.body.len());
+        }
 
-        let content_length = get_content_length_from_headers(&r.headers);
+        result
+    }
 
-        assert!(matches!(
-            content_length,
-            Err(GSIServerError::ParseContentLengthError(_))
-        ));
+    /// Process a stream and parse its body as JSON.
+    /// On failure to parse, the error keeps the raw body for inspection.
+    ///
+    /// In lenient mode, a body that fails to parse into D but is still valid JSON is passed to
+    /// the raw sink instead, and None is returned.
+    async fn read_state<D, S>(&self, socket: S) -> Result<Option<D>, GSIServerError>
+    where
+        D: DeserializeOwned,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        Ok(self
+            .read_state_with_request(socket)
+            .await?
+            .map(|(_, parsed)| parsed))
     }
 
-    #[test]
-    fn test_get_content_length_from_headers_not_a_number() {
-        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
-        let mut r = httparse::Request::new(&mut headers);
-        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: asdasd\r\n\r\n";
-        r.parse(request_bytes)
-            .expect("parsing the request should never fail");
+    /// Process a stream and parse its body as JSON like [`ConnectionConfig::read_state`],
+    /// also returning the request it was parsed from.
+    async fn read_state_with_request<D, S>(
+        &self,
+        socket: S,
+    ) -> Result<Option<(Request, D)>, GSIServerError>
+    where
+        D: DeserializeOwned,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut request = self.process(socket).await?;
+        let buf = request.body.clone();
 
-        let content_length = get_content_length_from_headers(&r.headers);
+        if self.require_dota_provider || self.lifecycle.is_some() {
+            // Bodies that aren't JSON objects are left to fail parsing like any other event.
+            let envelope = Envelope::parse(&buf);
+            if let (true, Ok(envelope)) = (self.require_dota_provider, &envelope) {
+                check_provider(envelope)?;
+            }
 
-        assert!(matches!(
-            content_length,
-            Err(GSIServerError::ParseContentLengthError(_))
-        ));
-    }
+            if let (Some(lifecycle), Ok(envelope)) = (&self.lifecycle, &envelope) {
+                lifecycle.observe(envelope);
+            }
+            request.envelope = envelope.ok();
+        }
 
-    #[tokio::test]
-    async fn test_process() {
-        let listener = TcpListener::bind(TEST_URI)
-            .await
-            .expect("failed to bind to address");
-        let local_addr = listener.local_addr().unwrap();
-        let sample_request = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: 173\r\n\r\n{\n\t\"provider\": {\n\t\t\"name\": \"Dota 2\",\n\t\t\"appid\": 570,\n\t\t\"version\": 47,\n\t\t\"timestamp\": 1688514013\n\t},\n\t\"player\": {\n\n\t},\n\t\"draft\": {\n\n\t},\n\t\"auth\": {\n\t\t\"token\": \"hello1234\"\n\t}\n}";
-        let expected = b"{\n\t\"provider\": {\n\t\t\"name\": \"Dota 2\",\n\t\t\"appid\": 570,\n\t\t\"version\": 47,\n\t\t\"timestamp\": 1688514013\n\t},\n\t\"player\": {\n\n\t},\n\t\"draft\": {\n\n\t},\n\t\"auth\": {\n\t\t\"token\": \"hello1234\"\n\t}\n}";
+        let source = match spans::parse(&buf) {
+            Ok(parsed) => {
+                if let Some(throttle) = &self.throttle {
+                    if !throttle.allow() {
+                        log::debug!("Dropping event received within the minimum interval");
+                        return Ok(None);
+                    }
+                }
 
-        tokio::spawn(async move {
-            if let Ok((mut stream, _)) = listener.accept().await {
-                let _ = stream.write_all(sample_request).await;
-                let _ = stream.shutdown().await;
+                return Ok(Some((request, parsed)));
             }
-        });
+            Err(e) => e,
+        };
+        self.stats.record_parse_error();
 
-        let stream = TcpStream::connect(local_addr)
-            .await
-            .expect("failed to connect to address");
+        if self.lenient {
+            if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&buf) {
+                log::warn!(
+                    "Failed to parse JSON body, handling it as raw JSON: {}",
+                    source
+                );
+                self.raw.send(value);
+                return Ok(None);
+            }
+        }
+
+        Err(GSIServerError::ParseBodyError { body: buf, source })
+    }
+
+    /// Log an error that made a connection task fail, and send it to the error sink.
+    /// A closed socket is part of the normal churn of Dota connections, so it's only logged
+    /// at debug level.
+    fn report(&self, error: GSIServerError) {
+        match error {
+            GSIServerError::SocketClosed => log::debug!("{}", error),
+            _ => log::error!("{}", error),
+        }
+        self.errors.send(error);
+    }
+
+    /// Record the latency of an event accepted at the given instant, once it has been handled.
+    fn record_handled(&self, accepted: Instant) {
+        self.stats.record_latency(accepted.elapsed());
+    }
+
+    /// Report the error returned by a handler, if any.
+    fn report_handled(&self, result: Result<(), HandlerError>) {
+        if let Err(e) = result {
+            self.report(GSIServerError::HandlerError(e));
+        }
+    }
+}
+
+/// The address of the peer of a connection accepted by a [`GSIServer`].
+///
+/// Which variants exist depends on the platform, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Peer {
+    Tcp(SocketAddr),
+    /// A peer connected to a Unix domain socket, with the path it's bound to, if any.
+    /// Clients usually connect without binding, so their address is unnamed.
+    #[cfg(unix)]
+    Unix(Option<PathBuf>),
+}
+
+impl fmt::Display for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Peer::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            Peer::Unix(Some(path)) => write!(f, "{}", path.display()),
+            #[cfg(unix)]
+            Peer::Unix(None) => write!(f, "(unnamed)"),
+        }
+    }
+}
+
+/// A listener bound by a [`GSIServer`], either to a TCP address or to a Unix domain socket.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Listener::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "{}", addr),
+                Err(_) => write!(f, "unknown address"),
+            },
+            #[cfg(unix)]
+            Listener::Unix(_, path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A connection accepted by a [`Listener`].
+/// Reads and writes are forwarded to the underlying stream.
+enum Socket {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl AsyncRead for Socket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Socket::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+            #[cfg(unix)]
+            Socket::Unix(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Socket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Socket::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+            #[cfg(unix)]
+            Socket::Unix(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Socket::Tcp(s) => Pin::new(s).poll_flush(cx),
+            #[cfg(unix)]
+            Socket::Unix(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Socket::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+            #[cfg(unix)]
+            Socket::Unix(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+/// A server that handles GameState Integration requests from Dota.
+/// The URI used in the configuration file must be the same URI used when creating a new [`GSIServer`].
+pub struct GSIServer {
+    uri: String,
+    #[cfg(unix)]
+    unix_path: Option<PathBuf>,
+    tcp_nodelay: bool,
+    listen_backlog: Option<u32>,
+    concurrency: Option<Arc<Semaphore>>,
+    health_uri: Option<String>,
+    /// Never sent to: dropping it with the server stops the tasks serving alongside it.
+    shutdown: watch::Sender<()>,
+    connection: ConnectionConfig,
+}
+
+impl Default for GSIServer {
+    fn default() -> Self {
+        GSIServer {
+            uri: "127.0.0.1:3000".to_owned(),
+            #[cfg(unix)]
+            unix_path: None,
+            tcp_nodelay: true,
+            listen_backlog: None,
+            concurrency: None,
+            health_uri: None,
+            shutdown: watch::channel(()).0,
+            connection: ConnectionConfig::default(),
+        }
+    }
+}
+
+impl GSIServer {
+    /// Create a new GSIServer with given URI.
+    pub fn new(uri: &str) -> Self {
+        GSIServer {
+            uri: uri.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new GSIServer listening on a Unix domain socket at path, instead of a TCP port.
+    /// Binding fails if a file already exists at path, so stale sockets must be removed first.
+    #[cfg(unix)]
+    pub fn unix<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+
+        GSIServer {
+            uri: path.display().to_string(),
+            unix_path: Some(path),
+            ..Default::default()
+        }
+    }
+
+    /// Set TCP_NODELAY on every accepted connection. Defaults to true.
+    /// Dota sends many small requests, so responses shouldn't wait on Nagle's algorithm.
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Set the maximum number of pending connections in the listen queue.
+    /// When not set, the platform default used by [`TcpListener::bind`] applies.
+    pub fn listen_backlog(mut self, backlog: u32) -> Self {
+        self.listen_backlog = Some(backlog);
+        self
+    }
+
+    /// Process at most max_concurrent connections at the same time. Defaults to no limit, and a
+    /// max_concurrent of 0 also means no limit.
+    /// Once the limit is reached, new connections wait in the listen queue until a running
+    /// connection finishes, including the time spent by handlers.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.concurrency = match max_concurrent {
+            0 => None,
+            n => Some(Arc::new(Semaphore::new(n))),
+        };
+        self
+    }
+
+    /// Terminate TLS on accepted connections, for Dota configured with an `https://` URI.
+    /// The certificate chain and private key are loaded from the PEM files at cert_path and
+    /// key_path, failing with [`GSIServerError::TlsError`] if they can't be loaded.
+    ///
+    /// A self-signed certificate for localhost can be generated with:
+    ///
+    /// ```sh
+    /// openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes -days 365 \
+    ///     -subj "/CN=localhost" \
+    ///     -addext "subjectAltName=DNS:localhost,IP:127.0.0.1" \
+    ///     -addext "basicConstraints=critical,CA:FALSE" \
+    ///     -keyout localhost.key -out localhost.crt
+    /// ```
+    ///
+    /// The certificate must then be trusted by the machine running Dota.
+    #[cfg(feature = "tls")]
+    pub fn with_tls<P: AsRef<Path>>(
+        mut self,
+        cert_path: P,
+        key_path: P,
+    ) -> Result<Self, GSIServerError> {
+        self.connection.tls = Some(tls::Tls::load(cert_path.as_ref(), key_path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Statistics of this server, shared with every connection it accepts.
+    /// Keep a handle before running the server to read them while it runs.
+    pub fn stats(&self) -> Arc<ServerStats> {
+        self.connection.stats.clone()
+    }
+
+    /// Answer health checks on a second listener bound to uri, without sending a Dota event.
+    ///
+    /// Any GET request is answered with `200 OK` and a JSON body with the number of events
+    /// processed, in `events_processed`, and the seconds since the last event was processed,
+    /// in `seconds_since_last_event`, which is null until the first event.
+    /// Health checks share the read timeout set with [`GSIServer::with_read_timeout`], and stop
+    /// being answered once the server is dropped.
+    pub fn with_health_endpoint(mut self, uri: &str) -> Self {
+        self.health_uri = Some(uri.to_owned());
+        self
+    }
+
+    /// Call callback when no event is received within timeout, meaning Dota is no longer
+    /// connected. Set timeout longer than the `heartbeat` in Dota's configuration file.
+    /// The callback is called once per period without events.
+    pub fn with_heartbeat_timeout(
+        mut self,
+        timeout: Duration,
+        callback: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        let on_timeout = Sink(Some(Arc::new(move |()| callback())));
+        self.connection.heartbeat = Some(Arc::new(Heartbeat::new(timeout, on_timeout)));
+        self
+    }
+
+    /// Drop events received less than interval after the last event that was handled.
+    /// This protects expensive handlers from a proxy or replay flooding the server, like
+    /// Dota's own `throttle` setting does.
+    ///
+    /// Throttling is global, across every connection and auth token. Dropped events are still
+    /// answered with the usual response, so Dota doesn't retry them.
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.connection.throttle = Some(Arc::new(Throttle::new(interval)));
+        self
+    }
+
+    /// Allocate request buffers with the given capacity, instead of
+    /// [`INITIAL_REQUEST_BUFFER_CAPACITY_BYTES`].
+    ///
+    /// With every data block enabled, Dota sends 50-60kb per event, and spectator feeds are
+    /// larger, so the default buffer grows at least once for each new connection. Pre-sizing
+    /// buffers to the expected payload, like 64kb, saves that reallocation. Buffers are
+    /// recycled across connections, so this is only allocated a few times.
+    pub fn with_initial_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.connection.buffers = BufferPool::new(capacity);
+        self
+    }
+
+    /// Override the raw HTTP response written back to Dota after every request.
+    /// The response must include a 2xx status line, otherwise Dota will retry the request.
+    /// Defaults to [`OK`].
+    pub fn with_response(mut self, response: &str) -> Self {
+        self.connection.response = Arc::from(response);
+        self
+    }
+
+    /// Drop connections that take longer than timeout to send a complete request.
+    /// This mirrors the `timeout` in Dota's configuration file. Defaults to no timeout.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Reject requests declaring a body larger than max_body_bytes.
+    /// Defaults to [`DEFAULT_MAX_BODY_BYTES`]. Request lines and headers are limited separately,
+    /// to 8 KiB, and longer ones are rejected with [`GSIServerError::IncompleteHeaders`].
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.connection.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Call sink with every error that makes a connection task fail, like a body that can't
+    /// be parsed. Errors are always logged, whether a sink is set or not.
+    pub fn on_error(mut self, sink: impl Fn(GSIServerError) + Send + Sync + 'static) -> Self {
+        self.connection.errors = Sink(Some(Arc::new(sink)));
+        self
+    }
+
+    /// Don't drop events that fail to parse into the handler's type.
+    ///
+    /// Dota adds and changes fields with every patch, and a single field with an unexpected type
+    /// fails the whole event. In lenient mode, such events are parsed into a
+    /// [`serde_json::Value`] instead and passed to the callback set with [`GSIServer::on_raw`].
+    /// Fields prone to this are counters that grow large in long or custom games, like a
+    /// player's `net_worth` or `commands_issued`, or a hero's `health` and `mana`.
+    pub fn lenient(mut self) -> Self {
+        self.connection.lenient = true;
+        self
+    }
+
+    /// Reject events not sent by Dota 2, like events from another game's integration or random
+    /// requests sent to the same port, with [`GSIServerError::UnexpectedProvider`].
+    /// Events are accepted when their provider block has the name and app ID of Dota 2.
+    /// Bodies that aren't JSON objects fail to parse like any other event, with
+    /// [`GSIServerError::ParseBodyError`].
+    ///
+    /// The provider is checked after the request is read, so rejected events are still answered
+    /// with `200 OK`, and counted as events in [`GSIServer::stats`] and as heartbeats.
+    pub fn require_dota_provider(mut self) -> Self {
+        self.connection.require_dota_provider = true;
+        self
+    }
+
+    /// Call callback with the raw JSON of events that failed to parse in lenient mode.
+    pub fn on_raw(mut self, callback: impl Fn(serde_json::Value) + Send + Sync + 'static) -> Self {
+        self.connection.raw = Sink(Some(Arc::new(callback)));
+        self
+    }
+
+    /// Call callback with the [`LifecycleEvent`]s derived from every event received: when a
+    /// match starts or ends, and when the player returns to the menu.
+    ///
+    /// Every event is compared with the previous one received by this server, whatever its
+    /// handler, including events dropped by [`GSIServer::with_min_interval`].
+    pub fn on_lifecycle(
+        mut self,
+        callback: impl Fn(LifecycleEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.connection.lifecycle = Some(Arc::new(Lifecycle::new(callback)));
+        self
+    }
+
+    /// Resolve this server's URI into the first address it points to.
+    /// The URI may be copied as is from Dota's configuration file, like `http://127.0.0.1:3000/`.
+    async fn resolve(&self) -> Result<SocketAddr, GSIServerError> {
+        let host = normalize_uri(&self.uri);
+        log::debug!("Normalized URI {:?} to bind address {}", self.uri, host);
+
+        match tokio::net::lookup_host(host).await {
+            Ok(mut addrs) => match addrs.next() {
+                Some(addr) => Ok(addr),
+                None => Err(GSIServerError::InvalidUri(self.uri.clone())),
+            },
+            Err(e) => {
+                log::debug!("failed to resolve {}: {}", host, e);
+                Err(GSIServerError::InvalidUri(self.uri.clone()))
+            }
+        }
+    }
+
+    /// Bind a [`TcpListener`] to this server's URI, honoring the configured listen backlog.
+    async fn bind(&self) -> Result<TcpListener, GSIServerError> {
+        let addr = self.resolve().await?;
+
+        let backlog = match self.listen_backlog {
+            Some(b) => b,
+            None => return Ok(TcpListener::bind(addr).await?),
+        };
+
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        #[cfg(not(windows))]
+        socket.set_reuseaddr(true)?;
+        socket.bind(addr)?;
+
+        Ok(socket.listen(backlog)?)
+    }
+
+    /// Bind a [`Listener`], and start watching for heartbeat timeouts and serving health checks,
+    /// if configured.
+    async fn listen(&self) -> Result<Listener, GSIServerError> {
+        let listener = self.bind_listener().await?;
+        log::info!("Listening on: {}", listener);
+
+        if let Some(heartbeat) = &self.connection.heartbeat {
+            heartbeat.watch();
+        }
+
+        if let Some(uri) = &self.health_uri {
+            let health_listener = TcpListener::bind(normalize_uri(uri)).await?;
+            log::info!(
+                "Serving health checks on: {:?}",
+                health_listener.local_addr()
+            );
+            tokio::spawn(health::serve(
+                health_listener,
+                self.connection.stats.clone(),
+                self.connection.read_timeout,
+                self.shutdown.subscribe(),
+            ));
+        }
+
+        Ok(listener)
+    }
+
+    /// Bind a [`Listener`] to this server's Unix domain socket, if set, or to its URI otherwise.
+    async fn bind_listener(&self) -> Result<Listener, GSIServerError> {
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_path {
+            return Ok(Listener::Unix(UnixListener::bind(path)?, path.clone()));
+        }
+
+        Ok(Listener::Tcp(self.bind().await?))
+    }
+
+    /// Accept a new TCP connection, applying the configured socket options.
+    /// Connections that fail to be configured are dropped, returning None.
+    async fn accept_tcp(
+        &self,
+        listener: &TcpListener,
+    ) -> Result<Option<(TcpStream, SocketAddr)>, GSIServerError> {
+        let (socket, addr) = listener.accept().await?;
+
+        if let Err(e) = socket.set_nodelay(self.tcp_nodelay) {
+            log::warn!(
+                "Dropping connection from {}, failed to set TCP_NODELAY: {}",
+                addr,
+                e
+            );
+            return Ok(None);
+        }
+
+        Ok(Some((socket, addr)))
+    }
+
+    /// Wait until another connection can be processed, if the number of connections is limited.
+    /// The returned permit must be held until processing the connection finishes.
+    async fn acquire_permit(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.concurrency {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Accept a new connection from any [`Listener`], together with the address of its peer.
+    ///
+    /// Errors accepting a connection are usually transient, like running out of file
+    /// descriptors or a peer resetting the connection before it was accepted, so they are
+    /// reported and accepting is retried after [`ACCEPT_ERROR_BACKOFF`].
+    async fn accept(&self, listener: &Listener) -> (Socket, Peer) {
+        loop {
+            match self.try_accept(listener).await {
+                Ok(Some(accepted)) => return accepted,
+                Ok(None) => {}
+                Err(e) => {
+                    self.connection.report(e);
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                }
+            }
+        }
+    }
+
+    /// Accept a new connection, or None if it was dropped after being accepted.
+    async fn try_accept(
+        &self,
+        listener: &Listener,
+    ) -> Result<Option<(Socket, Peer)>, GSIServerError> {
+        match listener {
+            Listener::Tcp(listener) => {
+                let (socket, addr) = match self.accept_tcp(listener).await? {
+                    Some(accepted) => accepted,
+                    None => return Ok(None),
+                };
+                log::info!("Accepted: {}", addr);
+                self.connection.stats.record_connection(&addr);
+                Ok(Some((Socket::Tcp(socket), Peer::Tcp(addr))))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                let (socket, addr) = listener.accept().await?;
+                log::info!("Accepted: {}", path.display());
+                let peer = Peer::Unix(addr.as_pathname().map(Path::to_path_buf));
+                Ok(Some((Socket::Unix(socket), peer)))
+            }
+        }
+    }
+
+    /// Run the Game State Integration server in the background, yielding every parsed event as
+    /// a [`Stream`].
+    ///
+    /// Errors processing a connection are logged and sent to the sink set with
+    /// [`GSIServer::on_error`], like when running the server with a handler. An error binding
+    /// the listener is yielded instead, and the stream ends.
+    ///
+    /// When the consumer lags behind, the server stops accepting connections until it catches
+    /// up. Dropping the stream stops the server and closes its listener.
+    pub fn into_stream<D>(self) -> impl Stream<Item = Result<D, GSIServerError>>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            let listener = match self.listen().await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let _ = sender.send(Err(e)).await;
+                    return;
+                }
+            };
+
+            loop {
+                let next = async {
+                    // Reserving a slot before accepting pauses the server while the stream is
+                    // full.
+                    let slot = sender.clone().reserve_owned().await.ok()?;
+                    let permit = self.acquire_permit().await;
+                    let (socket, peer) = self.accept(&listener).await;
+                    Some((slot, permit, socket, peer))
+                };
+
+                let (slot, permit, socket, peer) = tokio::select! {
+                    _ = sender.closed() => break,
+                    next = next => match next {
+                        Some(next) => next,
+                        None => break,
+                    },
+                };
+                let accepted = Instant::now();
+
+                let connection = self.connection.clone();
+
+                spans::spawn_connection(&peer, async move {
+                    log::debug!("Task spawned");
+                    let _permit = permit;
+
+                    match connection.read_state(socket).await {
+                        Ok(Some(parsed)) => {
+                            slot.send(Ok(parsed));
+                            connection.record_handled(accepted);
+                        }
+                        Ok(None) => {}
+                        Err(e) => connection.report(e),
+                    }
+                });
+            }
+
+            log::debug!("Stream dropped, stopping server");
+        });
+
+        ReceiverStream::new(receiver)
+    }
+
+    /// Accept connections on listener forever, processing each one in its own task.
+    ///
+    /// As soon as a connection is accepted, process is called with a clone of the connection
+    /// options, the socket and its peer, returning the future run by the task. If the number of
+    /// concurrent connections is limited, the task holds a permit until the future completes.
+    async fn serve<F, U>(&self, listener: Listener, process: F) -> Result<(), GSIServerError>
+    where
+        F: Fn(ConnectionConfig, Socket, Peer) -> U,
+        U: Future<Output = ()> + Send + 'static,
+    {
+        loop {
+            let permit = self.acquire_permit().await;
+            let (socket, peer) = self.accept(&listener).await;
+            let task = process(self.connection.clone(), socket, peer.clone());
+
+            spans::spawn_connection(&peer, async move {
+                log::debug!("Task spawned");
+                let _permit = permit;
+                task.await
+            });
+        }
+    }
+
+    /// Run the Game State Integration server.
+    /// A handler function is taken to process the data sent by Dota 2.
+    pub async fn run<D, U>(
+        self,
+        handler: impl Fn(D) -> U + Sync + Send + Copy + 'static,
+    ) -> Result<(), GSIServerError>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+        U: Future + Send + Sync + 'static,
+        U::Output: Send,
+    {
+        let listener = self.listen().await?;
+
+        self.serve(listener, move |connection, socket, _| {
+            let accepted = Instant::now();
+
+            async move {
+                match connection.read_state(socket).await {
+                    Ok(Some(parsed)) => {
+                        spans::handle(handler(parsed)).await;
+                        connection.record_handled(accepted);
+                    }
+                    Ok(None) => {}
+                    Err(e) => connection.report(e),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Run the Game State Integration server.
+    /// A handler function is taken to process the data sent by Dota 2, together with a
+    /// handle to state shared by every invocation.
+    ///
+    /// Unlike [`GSIServer::run`], the handler can update shared state, like a counter or a
+    /// database pool, without being `Copy`. The handler is only allocated once, with each
+    /// invocation receiving a clone of the state handle.
+    pub async fn run_with<S, D, F, U>(self, state: Arc<S>, handler: F) -> Result<(), GSIServerError>
+    where
+        S: Send + Sync + 'static,
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+        F: Fn(Arc<S>, D) -> U + Send + Sync + 'static,
+        U: Future + Send + 'static,
+        U::Output: Send,
+    {
+        let listener = self.listen().await?;
+        let handler = Arc::new(handler);
+
+        self.serve(listener, move |connection, socket, _| {
+            let accepted = Instant::now();
+            let this_handler = handler.clone();
+            let this_state = state.clone();
+
+            async move {
+                match connection.read_state(socket).await {
+                    Ok(Some(parsed)) => {
+                        spans::handle(this_handler(this_state, parsed)).await;
+                        connection.record_handled(accepted);
+                    }
+                    Ok(None) => {}
+                    Err(e) => connection.report(e),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Run the Game State Integration server.
+    /// A boxed handler function is taken to process the data sent by Dota 2.
+    ///
+    /// Unlike [`GSIServer::run`], the handler doesn't have to be `Copy`, so it can capture
+    /// state like a database pool without writing a [`GameStateHandler`]. The trade-off is
+    /// allocating a boxed future for every event.
+    pub async fn run_boxed<D>(self, handler: BoxedHandler<D>) -> Result<(), GSIServerError>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let listener = self.listen().await?;
+
+        self.serve(listener, move |connection, socket, _| {
+            let accepted = Instant::now();
+            let this_handler = handler.clone();
+
+            async move {
+                match connection.read_state(socket).await {
+                    Ok(Some(parsed)) => {
+                        connection.report_handled(spans::handle(this_handler(parsed)).await);
+                        connection.record_handled(accepted);
+                    }
+                    Ok(None) => {}
+                    Err(e) => connection.report(e),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Run the Game State Integration server.
+    /// A handler function is taken to process the data sent by Dota 2.
+    pub async fn run_with_handler<D>(
+        self,
+        handler: impl GameStateHandler<D> + Send + Sync + Clone + 'static,
+    ) -> Result<(), GSIServerError>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let listener = self.listen().await?;
+
+        self.serve(listener, move |connection, socket, _| {
+            let accepted = Instant::now();
+            // Need to clone as handler will be moved by spawn.
+            let this_handler = handler.clone();
+
+            async move {
+                match connection.read_state(socket).await {
+                    Ok(Some(parsed)) => {
+                        connection.report_handled(spans::handle(this_handler.handle(parsed)).await);
+                        connection.record_handled(accepted);
+                    }
+                    Ok(None) => {}
+                    Err(e) => connection.report(e),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Run the Game State Integration server.
+    /// A handler taking the request that sent each event, with its HTTP headers and the
+    /// address of its peer, along with the data sent by Dota 2 is used to process it.
+    pub async fn run_with_headers_handler<D>(
+        self,
+        handler: impl GameStateHandlerWithHeaders<D> + Send + Sync + Clone + 'static,
+    ) -> Result<(), GSIServerError>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let listener = self.listen().await?;
+
+        self.serve(listener, move |connection, socket, peer| {
+            let accepted = Instant::now();
+            let this_handler = handler.clone();
+
+            async move {
+                let (request, parsed) = match connection.read_state_with_request(socket).await {
+                    Ok(Some(request)) => request,
+                    Ok(None) => return,
+                    Err(e) => return connection.report(e),
+                };
+
+                let request = RequestContext {
+                    headers: request.headers,
+                    peer,
+                };
+                connection
+                    .report_handled(spans::handle(this_handler.handle(request, parsed)).await);
+                connection.record_handled(accepted);
+            }
+        })
+        .await
+    }
+
+    /// Run the Game State Integration server, dispatching each event to the handler registered
+    /// for the token in its `auth` block. This allows a single server to receive events from
+    /// multiple Dota clients, like when casting two games, each configured with its own token.
+    ///
+    /// Events with a token that has no handler, or without a token, are handled by fallback.
+    /// Without a fallback, these events are dropped and logged as a warning.
+    pub async fn route_by_token<D>(
+        self,
+        routes: HashMap<String, BoxedHandler<D>>,
+        fallback: Option<BoxedHandler<D>>,
+    ) -> Result<(), GSIServerError>
+    where
+        D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    {
+        let listener = self.listen().await?;
+        let routes = Arc::new(routes);
+
+        self.serve(listener, move |connection, socket, _| {
+            let accepted = Instant::now();
+            let routes = routes.clone();
+            let fallback = fallback.clone();
+
+            async move {
+                let (request, parsed) = match connection.read_state_with_request(socket).await {
+                    Ok(Some(request)) => request,
+                    Ok(None) => return,
+                    Err(e) => return connection.report(e),
+                };
+
+                // The envelope was only parsed already if the provider or lifecycle is checked.
+                let envelope = request
+                    .envelope
+                    .or_else(|| Envelope::parse(&request.body).ok());
+                let handler = envelope
+                    .as_ref()
+                    .and_then(Envelope::auth_token)
+                    .and_then(|token| routes.get(token))
+                    .or(fallback.as_ref());
+
+                match handler {
+                    Some(handler) => {
+                        connection.report_handled(spans::handle(handler(parsed)).await);
+                        connection.record_handled(accepted);
+                    }
+                    // Tokens are secrets, so they are left out of the logs.
+                    None => log::warn!("Dropping event without a handler for its auth token"),
+                }
+            }
+        })
+        .await
+    }
+}
+
+/// A boxed future, as returned by a [`BoxedHandler`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A handler function with its type erased, as taken by [`GSIServer::run_boxed`].
+/// Handlers registered in a [`ServerBuilder`] are also stored like this, so handlers of
+/// different types can be registered together.
+pub type BoxedHandler<D> =
+    Arc<dyn Fn(D) -> BoxFuture<'static, Result<(), HandlerError>> + Send + Sync>;
+
+/// Builds a server that calls multiple handlers for every request sent by Dota.
+///
+/// Each request is parsed once into `D`, and every registered handler receives its own clone
+/// of the parsed state. Handlers are called sequentially, in the order they were registered:
+/// a handler only starts once the previous one has finished handling the same request.
+pub struct ServerBuilder<D> {
+    server: GSIServer,
+    handlers: Vec<BoxedHandler<D>>,
+}
+
+impl<D> From<GSIServer> for ServerBuilder<D> {
+    fn from(server: GSIServer) -> Self {
+        ServerBuilder {
+            server,
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<D> ServerBuilder<D>
+where
+    D: DeserializeOwned + std::fmt::Debug + Clone + Send + 'static,
+{
+    /// Create a new ServerBuilder listening on the given URI, with no handlers registered.
+    /// Use [`ServerBuilder::from`] to start from an already configured [`GSIServer`].
+    pub fn new(uri: &str) -> Self {
+        ServerBuilder::from(GSIServer::new(uri))
+    }
+
+    /// Register a handler to be called for every request, after all previously registered handlers.
+    pub fn register<H>(mut self, handler: H) -> Self
+    where
+        H: GameStateHandler<D> + Send + Sync + Clone + 'static,
+    {
+        self.handlers
+            .push(Arc::new(move |gs: D| handler.clone().handle(gs)));
+        self
+    }
+
+    /// Bind the listener, so binding errors are reported before running the server.
+    pub async fn start(self) -> Result<RunningServer<D>, GSIServerError> {
+        let listener = self.server.listen().await?;
+
+        if self.handlers.is_empty() {
+            log::warn!("No handlers registered, requests will be ignored");
+        }
+
+        Ok(RunningServer {
+            server: self.server,
+            handlers: Arc::from(self.handlers),
+            listener,
+        })
+    }
+
+    /// Run the Game State Integration server, calling every registered handler.
+    pub async fn run_forever(self) -> Result<(), GSIServerError> {
+        self.start().await?.run_forever().await
+    }
+}
+
+/// A server started by [`ServerBuilder::start`], already bound to its URI.
+pub struct RunningServer<D> {
+    server: GSIServer,
+    handlers: Arc<[BoxedHandler<D>]>,
+    listener: Listener,
+}
+
+impl<D> RunningServer<D>
+where
+    D: DeserializeOwned + std::fmt::Debug + Clone + Send + 'static,
+{
+    /// The local address the server is listening on.
+    /// Useful when binding to port 0 to let the OS pick a free port.
+    /// Servers listening on a Unix domain socket have no address, so this fails for them.
+    pub fn local_addr(&self) -> Result<SocketAddr, GSIServerError> {
+        match &self.listener {
+            Listener::Tcp(listener) => Ok(listener.local_addr()?),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => Err(GSIServerError::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("listening on Unix domain socket {}", path.display()),
+            ))),
+        }
+    }
+
+    /// Statistics of the running server. See [`GSIServer::stats`].
+    pub fn stats(&self) -> Arc<ServerStats> {
+        self.server.stats()
+    }
+
+    /// Accept connections forever, calling every registered handler for each request.
+    pub async fn run_forever(self) -> Result<(), GSIServerError> {
+        let handlers = self.handlers;
+
+        self.server
+            .serve(self.listener, move |connection, socket, _| {
+                let accepted = Instant::now();
+                let handlers = handlers.clone();
+
+                async move {
+                    let parsed: D = match connection.read_state(socket).await {
+                        Ok(Some(parsed)) => parsed,
+                        Ok(None) => return,
+                        Err(e) => return connection.report(e),
+                    };
+
+                    // A failing handler doesn't prevent the following handlers from running.
+                    if let Some((last, rest)) = handlers.split_last() {
+                        for handler in rest {
+                            connection.report_handled(spans::handle(handler(parsed.clone())).await);
+                        }
+                        connection.report_handled(spans::handle(last(parsed)).await);
+                    }
+                    connection.record_handled(accepted);
+                }
+            })
+            .await
+    }
+}
+
+/// Check the provider block of a request body, as read into its envelope, was sent by Dota 2.
+fn check_provider(envelope: &Envelope) -> Result<(), GSIServerError> {
+    match &envelope.provider {
+        Some(provider) if provider.is_dota() => Ok(()),
+        Some(provider) => Err(GSIServerError::UnexpectedProvider(format!(
+            "{} (app ID {})",
+            provider,
+            provider.app_id()
+        ))),
+        None => Err(GSIServerError::UnexpectedProvider(
+            "missing provider".to_owned(),
+        )),
+    }
+}
+
+/// Strip the scheme and path from a URI, leaving only the host and port to bind to.
+/// For example, both `http://127.0.0.1:3000/` and `127.0.0.1:3000` become `127.0.0.1:3000`.
+fn normalize_uri(uri: &str) -> &str {
+    let uri = uri.trim();
+    let uri = uri
+        .strip_prefix("http://")
+        .or_else(|| uri.strip_prefix("https://"))
+        .unwrap_or(uri);
+
+    match uri.find('/') {
+        Some(i) => &uri[..i],
+        None => uri,
+    }
+}
+
+/// Map an error reading part of a request from a socket.
+/// Steam resets connections it's done with, even mid-request, so a reset or aborted
+/// connection is reported as [`GSIServerError::SocketClosed`] rather than a socket error.
+fn read_error(e: io::Error, part: &str) -> GSIServerError {
+    match e.kind() {
+        io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted => {
+            log::debug!("connection closed while reading {}: {}", part, e);
+            GSIServerError::SocketClosed
+        }
+        _ => {
+            log::error!("failed to read {} from socket: {}", part, e);
+            GSIServerError::from(e)
+        }
+    }
+}
+
+/// Process a stream, like a [`TcpStream`].
+/// Ensures the stream's contents can be parsed and returns an appropiate response to Dota.
+pub async fn process<S>(socket: S) -> Result<Bytes, GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    process_with_response(socket, OK.as_bytes()).await
+}
+
+/// Process a stream, writing back the given raw HTTP response instead of [`OK`].
+/// Bodies larger than [`DEFAULT_MAX_BODY_BYTES`] are rejected.
+pub async fn process_with_response<S>(
+    mut socket: S,
+    response: &[u8],
+) -> Result<Bytes, GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
+    read_request(&mut socket, &mut buf, response, DEFAULT_MAX_BODY_BYTES).await
+}
+
+/// Process a stream like [`process`], but return the whole request instead of only its body.
+///
+/// The request is returned with the length of its request line and headers, and the length of
+/// its body: the body starts at the first offset and spans the second. This allows reading
+/// headers that [`process`] discards, like one injected by a proxy. Bodies sent with
+/// `Content-Encoding: gzip` are returned as received, without decompressing them.
+pub async fn process_raw<S>(mut socket: S) -> Result<(usize, usize, BytesMut), GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
+    let raw =
+        read_raw_request(&mut socket, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES).await?;
+
+    Ok((raw.request_length, raw.content_length, raw.request))
+}
+
+/// Read a single request from a stream and write back the given raw HTTP response.
+///
+/// Bytes already in buf are parsed before reading more from the socket. Only the request's
+/// body, exactly Content-Length bytes long, is returned: any trailing bytes that belong to a
+/// pipelined request are left in buf, so calling this again with the same buffer reads the
+/// next request on a keep-alive connection.
+///
+/// The body is returned frozen, so it can be cheaply cloned and shared.
+///
+/// Requests with a body larger than max_body_bytes fail with [`GSIServerError::BodyTooLarge`]
+/// before any memory is reserved for the body.
+pub async fn read_request<S>(
+    socket: &mut S,
+    buf: &mut BytesMut,
+    response: &[u8],
+    max_body_bytes: usize,
+) -> Result<Bytes, GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    read_request_parts(socket, buf, response, max_body_bytes)
+        .await
+        .map(|request| request.body)
+}
+
+/// A request read by a server, split into its headers and its body.
+struct Request {
+    headers: Headers,
+    body: Bytes,
+    /// The envelope of the body, if it was parsed while reading its state.
+    envelope: Option<Envelope>,
+}
+
+/// Read a single request like [`read_request`], keeping its headers.
+async fn read_request_parts<S>(
+    socket: &mut S,
+    buf: &mut BytesMut,
+    response: &[u8],
+    max_body_bytes: usize,
+) -> Result<Request, GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut raw = read_raw_request(socket, buf, response, max_body_bytes).await?;
+    let body = raw.request.split_off(raw.request_length).freeze();
+    let body = if raw.gzip_encoded {
+        decode_gzip(body, max_body_bytes)?
+    } else {
+        body
+    };
+
+    Ok(Request {
+        headers: raw.headers,
+        body,
+        envelope: None,
+    })
+}
+
+/// A request read by [`read_raw_request`], split from the connection's buffer.
+struct RawRequest {
+    /// Length of the request line and headers, where the body starts.
+    request_length: usize,
+    content_length: usize,
+    gzip_encoded: bool,
+    headers: Headers,
+    request: BytesMut,
+}
+
+/// Read a single request like [`read_request`], returning it whole.
+async fn read_raw_request<S>(
+    socket: &mut S,
+    buf: &mut BytesMut,
+    response: &[u8],
+    max_body_bytes: usize,
+) -> Result<RawRequest, GSIServerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request_length: usize;
+    let content_length: usize;
+    let gzip_encoded: bool;
+    let parsed_headers: Headers;
+
+    loop {
+        if !buf.is_empty() {
+            let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+            let mut r = httparse::Request::new(&mut headers);
+
+            match r.parse(buf) {
+                Ok(httparse::Status::Complete(_)) if r.method != Some("POST") => {
+                    // Like a browser opening the URI of the server.
+                    let method = r.method.unwrap_or_default().to_owned();
+                    socket.write_all(METHOD_NOT_ALLOWED.as_bytes()).await?;
+                    return Err(GSIServerError::UnexpectedMethod(method));
+                }
+                Ok(httparse::Status::Complete(size)) => {
+                    request_length = size;
+                    gzip_encoded = find_header(&headers, "Content-Encoding")
+                        .is_some_and(|v| v.eq_ignore_ascii_case(b"gzip"));
+                    content_length = match get_content_length_from_headers(&headers) {
+                        Ok(n) => n,
+                        // Without a Content-Length, fall back to the body that was already read.
+                        Err(e) if find_header(&headers, "Content-Length").is_none() => {
+                            if buf.len() <= size {
+                                return Err(e);
+                            }
+                            log::debug!("Content-Length header not found, using buffered body");
+                            buf.len() - size
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    parsed_headers = Headers::from_parsed(&headers);
+                    break;
+                }
+                Ok(httparse::Status::Partial) => {
+                    if buf.len() >= MAX_REQUEST_HEAD_BYTES {
+                        log::error!(
+                            "rejecting request with headers longer than {} bytes",
+                            MAX_REQUEST_HEAD_BYTES
+                        );
+                        return Err(GSIServerError::IncompleteHeaders);
+                    }
+                    log::debug!("partial request parsed, need to read more");
+                }
+                Err(e) => {
+                    log::error!("failed to parse request: {}", e);
+                    return Err(GSIServerError::from(e));
+                }
+            };
+        }
+
+        match socket.read_buf(buf).await {
+            Ok(0) => return Err(GSIServerError::SocketClosed),
+            Ok(n) => n,
+            Err(e) => return Err(read_error(e, "request")),
+        };
+    }
+
+    if content_length > max_body_bytes {
+        log::error!(
+            "rejecting request with a body of {} bytes, limit is {}",
+            content_length,
+            max_body_bytes
+        );
+        return Err(GSIServerError::BodyTooLarge {
+            declared: content_length,
+            limit: max_body_bytes,
+        });
+    }
+
+    let total_length = request_length + content_length;
+    if buf.len() < total_length {
+        buf.reserve(total_length - buf.len());
+    }
+
+    while buf.len() < total_length {
+        match socket.read_buf(buf).await {
+            Ok(0) => return Err(GSIServerError::SocketClosed),
+            Ok(n) => n,
+            Err(e) => return Err(read_error(e, "body")),
+        };
+    }
+
+    if let Err(e) = socket.write_all(response).await {
+        log::error!("failed to write to socket: {}", e);
+        return Err(GSIServerError::from(e));
+    };
+
+    Ok(RawRequest {
+        request_length,
+        content_length,
+        gzip_encoded,
+        headers: parsed_headers,
+        request: buf.split_to(total_length),
+    })
+}
+
+/// The request line of a request without a body, as read by [`read_request_line`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RequestLine {
+    pub(crate) method: String,
+    pub(crate) path: String,
+}
+
+/// Read the request line and headers of a request without a body, like a health check or a
+/// metrics scrape, failing once read_timeout is exceeded or past [`MAX_REQUEST_HEAD_BYTES`].
+pub(crate) async fn read_request_line<S>(
+    socket: &mut S,
+    read_timeout: Option<Duration>,
+) -> Result<RequestLine, GSIServerError>
+where
+    S: AsyncRead + Unpin,
+{
+    let reading = async {
+        let mut buf = BytesMut::with_capacity(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
+
+        loop {
+            if buf.len() >= MAX_REQUEST_HEAD_BYTES {
+                return Err(GSIServerError::IncompleteHeaders);
+            }
+
+            if socket.read_buf(&mut buf).await? == 0 {
+                return Err(GSIServerError::SocketClosed);
+            }
+
+            let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+            let mut r = httparse::Request::new(&mut headers);
+
+            if let httparse::Status::Complete(_) = r.parse(&buf)? {
+                return Ok(RequestLine {
+                    method: r.method.unwrap_or_default().to_owned(),
+                    path: r.path.unwrap_or_default().to_owned(),
+                });
+            }
+        }
+    };
+
+    match read_timeout {
+        None => reading.await,
+        Some(timeout) => tokio::time::timeout(timeout, reading)
+            .await
+            .map_err(|_| GSIServerError::ReadTimeout(timeout))?,
+    }
+}
+
+/// Decompress a body sent with `Content-Encoding: gzip`, like when a reverse proxy compresses it.
+/// Bodies that decompress to more than max_body_bytes are rejected.
+#[cfg(feature = "gzip")]
+fn decode_gzip(body: Bytes, max_body_bytes: usize) -> Result<Bytes, GSIServerError> {
+    use std::io::Read;
+
+    let mut decoded = Vec::with_capacity(body.len());
+    flate2::read::GzDecoder::new(body.as_ref())
+        .take(max_body_bytes as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(GSIServerError::DecodeBodyError)?;
+
+    if decoded.len() > max_body_bytes {
+        return Err(GSIServerError::DecodeBodyError(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decoded body exceeds the limit of {} bytes", max_body_bytes),
+        )));
+    }
+
+    Ok(Bytes::from(decoded))
+}
+
+/// Without the gzip feature, gzip encoded bodies are rejected, as they can't be parsed.
+#[cfg(not(feature = "gzip"))]
+fn decode_gzip(_body: Bytes, _max_body_bytes: usize) -> Result<Bytes, GSIServerError> {
+    Err(GSIServerError::DecodeBodyError(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "enable the gzip feature",
+    )))
+}
+
+/// Find the value of the first header with the given name.
+/// Header names are compared case-insensitively, as HTTP header names are case-insensitive.
+fn find_header<'a>(headers: &[httparse::Header<'a>], name: &str) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value)
+}
+
+/// Extract Content-Length value from a list of HTTP headers.
+pub fn get_content_length_from_headers(
+    headers: &[httparse::Header],
+) -> Result<usize, GSIServerError> {
+    match find_header(headers, "Content-Length") {
+        Some(value) => {
+            let str_length = match std::str::from_utf8(value) {
+                Ok(s) => s,
+                Err(e) => {
+                    return Err(GSIServerError::ParseContentLengthError(format!(
+                        "failed to parse bytes as str: {}",
+                        e
+                    )))
+                }
+            };
+            match str_length.parse::<usize>() {
+                Ok(n) => Ok(n),
+                Err(e) => Err(GSIServerError::ParseContentLengthError(format!(
+                    "failed to parse str into usize: {}",
+                    e
+                ))),
+            }
+        }
+        None => Err(GSIServerError::ParseContentLengthError(
+            "Content-Length header not found".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    const TEST_URI: &str = "127.0.0.1:0";
+
+    /// Find a free local address for a server that binds its own listener.
+    fn free_addr() -> SocketAddr {
+        let listener = std::net::TcpListener::bind(TEST_URI).expect("failed to bind to address");
+        listener.local_addr().unwrap()
+    }
+
+    /// Connect to a server spawned on addr, retrying for a second while it starts listening.
+    async fn connect(addr: SocketAddr) -> TcpStream {
+        let start = std::time::Instant::now();
+
+        loop {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => return stream,
+                Err(_) if start.elapsed() < Duration::from_secs(1) => {
+                    tokio::time::sleep(Duration::from_millis(10)).await
+                }
+                Err(e) => panic!("failed to connect to address: {}", e),
+            }
+        }
+    }
+    /// Large enough to write any test request to a duplex stream before it is read.
+    const DUPLEX_CAPACITY_BYTES: usize = 64 * 1024;
+
+    #[test]
+    fn test_get_content_length_from_headers() {
+        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+        let mut r = httparse::Request::new(&mut headers);
+        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: 54943\r\n\r\n";
+        r.parse(request_bytes)
+            .expect("parsing the request should never fail");
+
+        let expected = 54943_usize;
+        let content_length =
+            get_content_length_from_headers(r.headers).expect("failed to get Content-Length");
+
+        assert_eq!(content_length, expected);
+    }
+
+    #[test]
+    fn test_get_content_length_from_headers_not_found() {
+        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+        let mut r = httparse::Request::new(&mut headers);
+        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\n\r\n";
+        r.parse(request_bytes)
+            .expect("parsing the request should never fail");
+
+        let content_length = get_content_length_from_headers(r.headers);
+
+        assert!(matches!(
+            content_length,
+            Err(GSIServerError::ParseContentLengthError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_content_length_from_headers_not_a_number() {
+        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+        let mut r = httparse::Request::new(&mut headers);
+        let request_bytes = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: asdasd\r\n\r\n";
+        r.parse(request_bytes)
+            .expect("parsing the request should never fail");
+
+        let content_length = get_content_length_from_headers(r.headers);
+
+        assert!(matches!(
+            content_length,
+            Err(GSIServerError::ParseContentLengthError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_content_length_from_headers_case_insensitive() {
+        for request_bytes in [
+            b"POST / HTTP/1.1\r\nHost: 127.0.0.1:3000\r\ncontent-length: 54943\r\n\r\n".as_ref(),
+            b"POST / HTTP/1.1\r\nHost: 127.0.0.1:3000\r\nCONTENT-LENGTH: 54943\r\n\r\n".as_ref(),
+            b"POST / HTTP/1.1\r\nHost: 127.0.0.1:3000\r\ncOnTeNt-LeNgTh: 54943\r\n\r\n".as_ref(),
+        ] {
+            let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+            let mut r = httparse::Request::new(&mut headers);
+            r.parse(request_bytes)
+                .expect("parsing the request should never fail");
+
+            let content_length =
+                get_content_length_from_headers(r.headers).expect("failed to get Content-Length");
+
+            assert_eq!(content_length, 54943);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_without_content_length() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\n\r\n{\"auth\": {}}";
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        client.write_all(sample_request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = process(stream).await.expect("processing failed");
+        assert_eq!(result.as_ref(), b"{\"auth\": {}}");
+    }
+
+    #[tokio::test]
+    async fn test_process_unexpected_method() {
+        let sample_request = b"GET / HTTP/1.1\r\nHost: 127.0.0.1:3000\r\n\r\n";
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        client.write_all(sample_request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = process(stream).await;
+        assert_eq!(
+            result,
+            Err(GSIServerError::UnexpectedMethod("GET".to_owned()))
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "rejected GET request, Dota only sends POST requests"
+        );
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_process() {
+        let sample_request = b"POST / HTTP/1.1\r\nuser-agent: Valve/Steam HTTP Client 1.0 (570)\r\nContent-Type: application/json\r\nHost: 127.0.0.1:3000\r\nAccept: text/html,*/*;q=0.9\r\naccept-encoding: gzip,identity,*;q=0\r\naccept-charset: ISO-8859-1,utf-8,*;q=0.7\r\nContent-Length: 173\r\n\r\n{\n\t\"provider\": {\n\t\t\"name\": \"Dota 2\",\n\t\t\"appid\": 570,\n\t\t\"version\": 47,\n\t\t\"timestamp\": 1688514013\n\t},\n\t\"player\": {\n\n\t},\n\t\"draft\": {\n\n\t},\n\t\"auth\": {\n\t\t\"token\": \"hello1234\"\n\t}\n}";
+        let expected = b"{\n\t\"provider\": {\n\t\t\"name\": \"Dota 2\",\n\t\t\"appid\": 570,\n\t\t\"version\": 47,\n\t\t\"timestamp\": 1688514013\n\t},\n\t\"player\": {\n\n\t},\n\t\"draft\": {\n\n\t},\n\t\"auth\": {\n\t\t\"token\": \"hello1234\"\n\t}\n}";
+
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        client.write_all(sample_request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = process(stream).await.expect("processing failed");
+        assert_eq!(result.len(), expected.len());
+        assert_eq!(result.as_ref(), expected);
+
+        // Cloning the body shares the same memory instead of copying it.
+        let shared = result.clone();
+        assert_eq!(shared.as_ptr(), result.as_ptr());
+        assert_eq!(shared, result);
+    }
+
+    #[tokio::test]
+    async fn test_read_request_pipelined() {
+        let pipelined_requests = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 13\r\n\r\n{\"first\": 1}\nPOST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 13\r\n\r\n{\"second\": 2}";
+        let (mut client, mut stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        client.write_all(pipelined_requests).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut buf = BytesMut::new();
+
+        let first = read_request(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES)
+            .await
+            .expect("processing first request failed");
+        assert_eq!(first.len(), 13);
+        assert_eq!(first.as_ref(), b"{\"first\": 1}\n");
+
+        let second = read_request(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES)
+            .await
+            .expect("processing second request failed");
+        assert_eq!(second.as_ref(), b"{\"second\": 2}");
+        assert!(buf.is_empty());
+
+        let closed =
+            read_request(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES).await;
+        assert!(matches!(closed, Err(GSIServerError::SocketClosed)));
+    }
+
+    #[tokio::test]
+    async fn test_process_connection_reset() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(local_addr).await.unwrap();
+            stream
+                .write_all(b"POST / HTTP/1.1\r\nContent-Length: 64\r\n\r\n{\"partial\":")
+                .await
+                .unwrap();
+            // Dropping a socket with a zero linger sends a reset instead of closing it.
+            stream.set_zero_linger().unwrap();
+        });
+
+        let (socket, _) = listener.accept().await.unwrap();
+        client.await.unwrap();
+
+        assert_eq!(process(socket).await, Err(GSIServerError::SocketClosed));
+    }
+
+    #[tokio::test]
+    async fn test_process_closed_mid_body() {
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client
+            .write_all(b"POST / HTTP/1.1\r\nContent-Length: 64\r\n\r\n{\"partial\":")
+            .await
+            .unwrap();
+        drop(client);
+
+        assert_eq!(process(stream).await, Err(GSIServerError::SocketClosed));
+    }
+
+    #[test]
+    fn test_read_error() {
+        for kind in [
+            io::ErrorKind::ConnectionReset,
+            io::ErrorKind::ConnectionAborted,
+        ] {
+            assert_eq!(
+                read_error(io::Error::from(kind), "body"),
+                GSIServerError::SocketClosed
+            );
+        }
+
+        assert!(matches!(
+            read_error(io::Error::from(io::ErrorKind::InvalidData), "body"),
+            GSIServerError::SocketError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_process_raw() {
+        let sample_request = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nX-Match-Id: 7412\r\nContent-Length: 10\r\n\r\n{\"id\": 10}";
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(sample_request).await.unwrap();
+
+        let (request_length, content_length, request) =
+            process_raw(stream).await.expect("processing failed");
+
+        assert_eq!(request.len(), sample_request.len());
+        assert_eq!(request[request_length], b'{');
+        assert_eq!(content_length, 10);
+        assert_eq!(
+            &request[request_length..request_length + content_length],
+            b"{\"id\": 10}"
+        );
+
+        let mut headers = [httparse::EMPTY_HEADER; EXPECTED_NUMBER_OF_HEADERS];
+        httparse::Request::new(&mut headers)
+            .parse(&request[..request_length])
+            .unwrap();
+        assert_eq!(find_header(&headers, "X-Match-Id"), Some(&b"7412"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_process_response() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let custom_response = "HTTP/1.1 204 No Content\r\n\r\n";
+
+        for response in [OK, custom_response] {
+            let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+            client.write_all(sample_request).await.unwrap();
+            client.shutdown().await.unwrap();
+
+            process_with_response(stream, response.as_bytes())
+                .await
+                .expect("processing failed");
+
+            let mut written = Vec::new();
+            client.read_to_end(&mut written).await.unwrap();
+            assert_eq!(written, response.as_bytes());
+        }
+
+        assert_eq!(
+            OK,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_reuses_buffers() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let connection = ConnectionConfig::default();
+        let listener = TcpListener::bind(TEST_URI)
+            .await
+            .expect("failed to bind to address");
+        let local_addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                let mut stream = TcpStream::connect(local_addr).await.unwrap();
+                stream.write_all(sample_request).await.unwrap();
+                stream.shutdown().await.unwrap();
+                let mut response = Vec::new();
+                let _ = stream.read_to_end(&mut response).await;
+            }
+        });
+
+        for _ in 0..3 {
+            let (stream, _) = listener.accept().await.expect("failed to accept");
+            let request = connection.process(stream).await.expect("processing failed");
+            assert_eq!(request.body.as_ref(), b"{}");
+
+            // The buffer is returned to the pool after every request, and taken by the next one.
+            assert_eq!(connection.buffers.buffers.lock().unwrap().len(), 1);
+        }
+
+        let pool = BufferPool::default();
+        assert!(pool.take().capacity() >= INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
+        for _ in 0..MAX_POOLED_BUFFERS + 1 {
+            pool.give(BytesMut::new());
+        }
+        assert_eq!(pool.buffers.lock().unwrap().len(), MAX_POOLED_BUFFERS);
+    }
+
+    #[test]
+    fn test_initial_buffer_capacity() {
+        let server = GSIServer::new(TEST_URI).with_initial_buffer_capacity(64 * 1024);
+        let buf = server.connection.buffers.take();
+
+        assert!(buf.capacity() >= 64 * 1024);
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_min_interval() {
+        let interval = Duration::from_millis(100);
+        let server = GSIServer::new(TEST_URI).with_min_interval(interval);
+
+        let send = || async {
+            let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+            client
+                .write_all(b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}")
+                .await
+                .unwrap();
+
+            let parsed: Option<serde_json::Value> = server
+                .connection
+                .read_state(stream)
+                .await
+                .expect("processing failed");
+
+            // Dropped events are still answered.
+            let mut response = [0; 15];
+            client.read_exact(&mut response).await.unwrap();
+            assert_eq!(&response, b"HTTP/1.1 200 OK");
+
+            parsed.is_some()
+        };
+
+        let mut forwarded = 0;
+        for _ in 0..10 {
+            if send().await {
+                forwarded += 1;
+            }
+            tokio::time::advance(Duration::from_millis(5)).await;
+        }
+        assert_eq!(forwarded, 1);
+
+        tokio::time::advance(interval).await;
+        assert!(send().await);
+        assert!(!send().await);
+    }
+
+    #[tokio::test]
+    async fn test_on_lifecycle() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let server = GSIServer::new(TEST_URI).on_lifecycle(move |e| sink.lock().unwrap().push(e));
+
+        for game_state in ["PRE_GAME", "GAME_IN_PROGRESS"] {
+            let body = format!(
+                r#"{{"player": {{"activity": "playing"}}, "map": {{"game_state": "DOTA_GAMERULES_STATE_{}"}}}}"#,
+                game_state
+            );
+            let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+            client
+                .write_all(
+                    format!(
+                        "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+
+            let parsed: Option<serde_json::Value> =
+                server.connection.read_state(stream).await.unwrap();
+            assert!(parsed.is_some());
+        }
+
+        assert_eq!(*events.lock().unwrap(), vec![LifecycleEvent::MatchStarted]);
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let listener = TcpListener::bind(TEST_URI)
+            .await
+            .expect("failed to bind to address");
+        let local_addr = listener.local_addr().unwrap();
+        let timeout = Duration::from_millis(100);
+        let server = GSIServer::new(TEST_URI).with_read_timeout(timeout);
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(local_addr)
+                .await
+                .expect("failed to connect to address");
+            // Never finish sending the headers.
+            stream
+                .write_all(b"POST / HTTP/1.1\r\nContent-")
+                .await
+                .unwrap();
+
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await.unwrap();
+            response
+        });
+
+        let (stream, _) = listener.accept().await.expect("failed to accept");
+        let start = std::time::Instant::now();
+        let result = server.connection.process(stream).await;
+
+        assert!(matches!(result, Err(GSIServerError::ReadTimeout(t)) if t == timeout));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // The connection was closed without writing a response.
+        let response = client.await.unwrap();
+        assert!(response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_request_headers_too_long() {
+        let (mut client, mut stream) = tokio::io::duplex(2 * MAX_REQUEST_HEAD_BYTES);
+        let header = format!("X-Padding: {}", "a".repeat(MAX_REQUEST_HEAD_BYTES));
+        client.write_all(b"POST / HTTP/1.1\r\n").await.unwrap();
+        client.write_all(header.as_bytes()).await.unwrap();
+
+        let mut buf = BytesMut::new();
+        let result =
+            read_request(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES).await;
+
+        assert!(matches!(result, Err(GSIServerError::IncompleteHeaders)));
+        assert!(buf.len() < 2 * MAX_REQUEST_HEAD_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_read_request_body_too_large() {
+        let listener = TcpListener::bind(TEST_URI)
+            .await
+            .expect("failed to bind to address");
+        let local_addr = listener.local_addr().unwrap();
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 10000000\r\n\r\n{}";
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(local_addr)
+                .await
+                .expect("failed to connect to address");
+            stream.write_all(sample_request).await.unwrap();
+            stream.shutdown().await.unwrap();
+
+            // The connection may be reset as the server drops it without responding.
+            let mut response = Vec::new();
+            let _ = stream.read_to_end(&mut response).await;
+            response
+        });
+
+        let (mut stream, _) = listener.accept().await.expect("failed to accept");
+        let mut buf = BytesMut::with_capacity(INITIAL_REQUEST_BUFFER_CAPACITY_BYTES);
+        let result =
+            read_request(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES).await;
+        drop(stream);
+
+        assert!(matches!(
+            result,
+            Err(GSIServerError::BodyTooLarge {
+                declared: 10_000_000,
+                limit: DEFAULT_MAX_BODY_BYTES
+            })
+        ));
+        // Nothing was reserved for the declared body.
+        assert!(buf.capacity() < DEFAULT_MAX_BODY_BYTES);
+
+        let response = client.await.unwrap();
+        assert!(response.is_empty());
+    }
+
+    #[derive(Clone)]
+    struct RecordHandler {
+        name: &'static str,
+        calls: Arc<tokio::sync::Mutex<Vec<(&'static str, serde_json::Value)>>>,
+    }
+
+    #[async_trait]
+    impl GameStateHandler<serde_json::Value> for RecordHandler {
+        async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+            self.calls.lock().await.push((self.name, gs));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_builder_calls_every_handler_in_order() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}";
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let handler = |name| RecordHandler {
+            name,
+            calls: calls.clone(),
+        };
+
+        let running = ServerBuilder::new(TEST_URI)
+            .register(handler("logger"))
+            .register(handler("stats"))
+            .register(handler("websocket"))
+            .start()
+            .await
+            .expect("failed to start server");
+        let local_addr = running.local_addr().unwrap();
+        let stats = running.stats();
+        let server = tokio::spawn(running.run_forever());
+
+        let mut stream = TcpStream::connect(local_addr)
+            .await
+            .expect("failed to connect to address");
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+        let start = std::time::Instant::now();
+        // The latency is recorded once every handler has finished.
+        while stats.max_latency().is_none() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let calls = calls.lock().await;
+        let names: Vec<&str> = calls.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["logger", "stats", "websocket"]);
+        assert!(calls.iter().all(|(_, gs)| gs["id"] == 1));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.events, 1);
+        assert_eq!(snapshot.parse_errors, 0);
+        assert_eq!(snapshot.bytes_received, 9);
+        assert!(snapshot.last_event.is_some());
+        assert!(stats.p99_latency().unwrap() <= stats.max_latency().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_server_on_error() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 8\r\n\r\nnot json";
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_errors = errors.clone();
+
+        let server =
+            GSIServer::new(TEST_URI).on_error(move |e| sink_errors.lock().unwrap().push(e));
+        let running = ServerBuilder::from(server)
+            .register(RecordHandler {
+                name: "unreachable",
+                calls: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            })
+            .start()
+            .await
+            .expect("failed to start server");
+        let local_addr = running.local_addr().unwrap();
+        let server = tokio::spawn(running.run_forever());
+
+        let mut stream = TcpStream::connect(local_addr)
+            .await
+            .expect("failed to connect to address");
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let start = std::time::Instant::now();
+        while errors.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            GSIServerError::ParseBodyError { body, .. } if body.as_ref() == b"not json"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lenient_read_state() {
+        #[derive(serde::Deserialize, Debug)]
+        struct Strict {
+            #[allow(dead_code)]
+            net_worth: u16,
+        }
+
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 20\r\n\r\n{\"net_worth\": 70000}";
+        let raw = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let raw_values = raw.clone();
+        let server = GSIServer::new(TEST_URI);
+
+        for connection in [
+            server.connection.clone(),
+            GSIServer::new(TEST_URI)
+                .lenient()
+                .on_raw(move |v| raw_values.lock().unwrap().push(v))
+                .connection,
+        ] {
+            let listener = TcpListener::bind(TEST_URI)
+                .await
+                .expect("failed to bind to address");
+            let local_addr = listener.local_addr().unwrap();
+
+            tokio::spawn(async move {
+                let mut stream = TcpStream::connect(local_addr).await.unwrap();
+                stream.write_all(sample_request).await.unwrap();
+                stream.shutdown().await.unwrap();
+                let mut response = Vec::new();
+                let _ = stream.read_to_end(&mut response).await;
+            });
+
+            let (stream, _) = listener.accept().await.expect("failed to accept");
+            let result = connection.read_state::<Strict, _>(stream).await;
+
+            if connection.lenient {
+                assert!(matches!(result, Ok(None)));
+            } else {
+                assert!(matches!(result, Err(GSIServerError::ParseBodyError { .. })));
+            }
+        }
+
+        let raw = raw.lock().unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw[0]["net_worth"], 70000);
+    }
+
+    #[tokio::test]
+    async fn test_require_dota_provider() {
+        let connection = GSIServer::new(TEST_URI).require_dota_provider().connection;
+
+        for (body, accepted) in [
+            (
+                r#"{"provider": {"name": "Dota 2", "appid": 570, "version": 47, "timestamp": 1}}"#,
+                true,
+            ),
+            (
+                r#"{"provider": {"name": "Counter-Strike: Global Offensive", "appid": 730, "version": 13, "timestamp": 1}}"#,
+                false,
+            ),
+            (r#"{"hello": "world"}"#, false),
+        ] {
+            let request = format!(
+                "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+            client.write_all(request.as_bytes()).await.unwrap();
+            client.shutdown().await.unwrap();
+
+            let result = connection.read_state::<serde_json::Value, _>(stream).await;
+
+            if accepted {
+                assert!(matches!(result, Ok(Some(_))));
+            } else {
+                assert!(matches!(result, Err(GSIServerError::UnexpectedProvider(_))));
+            }
+        }
+
+        let body = "not json";
+        let request = format!(
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(request.as_bytes()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = connection.read_state::<serde_json::Value, _>(stream).await;
+        assert!(
+            matches!(result, Err(GSIServerError::ParseBodyError { body, .. }) if body == "not json")
+        );
+        assert_eq!(connection.stats.snapshot().parse_errors, 1);
+    }
+
+    #[test]
+    fn test_parse_body_error_display_is_truncated() {
+        let short = GSIServerError::ParseBodyError {
+            body: Bytes::from_static(b"{\"map\": 1"),
+            source: serde_json::from_str::<serde_json::Value>("{").unwrap_err(),
+        };
+        assert!(short.to_string().ends_with("{\"map\": 1"));
+
+        let body = Bytes::from(vec![b'a'; MAX_DISPLAYED_BODY_BYTES + 10]);
+        let long = GSIServerError::ParseBodyError {
+            body: body.clone(),
+            source: serde_json::from_slice::<serde_json::Value>(&body).unwrap_err(),
+        };
+        let displayed = long.to_string();
+
+        assert!(displayed.len() < MAX_DISPLAYED_BODY_BYTES + 150);
+        assert!(displayed.ends_with("... (10 more bytes)"));
+    }
+
+    #[test]
+    fn test_server_error_eq() {
+        assert_eq!(GSIServerError::SocketClosed, GSIServerError::SocketClosed);
+        assert_eq!(
+            GSIServerError::ParseContentLengthError("asdasd".to_owned()),
+            GSIServerError::ParseContentLengthError("asdasd".to_owned())
+        );
+        assert_ne!(
+            GSIServerError::ParseContentLengthError("asdasd".to_owned()),
+            GSIServerError::ParseContentLengthError("42".to_owned())
+        );
+        assert_ne!(
+            GSIServerError::IncompleteHeaders,
+            GSIServerError::SocketClosed
+        );
+        assert_eq!(
+            GSIServerError::from(io::Error::new(io::ErrorKind::BrokenPipe, "a")),
+            GSIServerError::from(io::Error::new(io::ErrorKind::BrokenPipe, "b"))
+        );
+        assert_ne!(
+            GSIServerError::from(io::Error::new(io::ErrorKind::BrokenPipe, "a")),
+            GSIServerError::from(io::Error::new(io::ErrorKind::TimedOut, "a"))
+        );
+
+        let body = Bytes::from_static(b"{");
+        assert_eq!(
+            GSIServerError::ParseBodyError {
+                body: body.clone(),
+                source: serde_json::from_str::<serde_json::Value>("{").unwrap_err(),
+            },
+            GSIServerError::ParseBodyError {
+                body,
+                source: serde_json::from_str::<serde_json::Value>("[").unwrap_err(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_normalize_uri() {
+        assert_eq!(normalize_uri("127.0.0.1:3000"), "127.0.0.1:3000");
+        assert_eq!(normalize_uri("http://127.0.0.1:3000/"), "127.0.0.1:3000");
+        assert_eq!(normalize_uri("http://127.0.0.1:53000/"), "127.0.0.1:53000");
+        assert_eq!(normalize_uri("https://localhost:3000"), "localhost:3000");
+        assert_eq!(normalize_uri("http://[::1]:3000/gsi"), "[::1]:3000");
+        assert_eq!(normalize_uri(" 127.0.0.1:3000/ "), "127.0.0.1:3000");
+        assert_eq!(normalize_uri("https://127.0.0.1:3000//"), "127.0.0.1:3000");
+    }
+
+    #[tokio::test]
+    async fn test_server_resolve() {
+        for (uri, expected) in [
+            ("http://127.0.0.1:3000/", "127.0.0.1:3000"),
+            ("127.0.0.1:3000", "127.0.0.1:3000"),
+            ("[::1]:3000", "[::1]:3000"),
+        ] {
+            let addr = GSIServer::new(uri)
+                .resolve()
+                .await
+                .expect("failed to resolve URI");
+            assert_eq!(addr, expected.parse::<SocketAddr>().unwrap());
+        }
+
+        let addr = GSIServer::new("http://localhost:3000/")
+            .resolve()
+            .await
+            .expect("failed to resolve URI");
+        assert!(addr.ip().is_loopback());
+
+        for uri in ["127.0.0.1", "http://", "not a uri:port"] {
+            let result = GSIServer::new(uri).resolve().await;
+            assert!(matches!(result, Err(GSIServerError::InvalidUri(u)) if u == uri));
+        }
+
+        let listener = GSIServer::new("http://127.0.0.1:0/")
+            .bind()
+            .await
+            .expect("failed to bind to address");
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+
+    #[tokio::test]
+    async fn test_server_socket_options() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+
+        for nodelay in [true, false] {
+            let server = GSIServer::new(TEST_URI)
+                .tcp_nodelay(nodelay)
+                .listen_backlog(16);
+            let listener = server.bind().await.expect("failed to bind to address");
+            let local_addr = listener.local_addr().unwrap();
+
+            let client = tokio::spawn(async move {
+                let mut stream = TcpStream::connect(local_addr)
+                    .await
+                    .expect("failed to connect to address");
+                stream.write_all(sample_request).await.unwrap();
+                stream.shutdown().await.unwrap();
+
+                let mut response = Vec::new();
+                stream.read_to_end(&mut response).await.unwrap();
+                response
+            });
+
+            let (stream, _) = server
+                .accept_tcp(&listener)
+                .await
+                .expect("failed to accept")
+                .expect("connection was dropped");
+            assert_eq!(stream.nodelay().unwrap(), nodelay);
+
+            let result = process(stream).await.expect("processing failed");
+            assert_eq!(result.as_ref(), b"{}");
+
+            let response = client.await.unwrap();
+            assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_server_unix_socket() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}";
+        let path = std::env::temp_dir().join(format!("dota-gsi-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+
+        let running = ServerBuilder::from(GSIServer::unix(&path))
+            .register(RecordHandler {
+                name: "unix",
+                calls: calls.clone(),
+            })
+            .start()
+            .await
+            .expect("failed to start server");
+        assert!(running.local_addr().is_err());
+        let server = tokio::spawn(running.run_forever());
+
+        let mut stream = UnixStream::connect(&path)
+            .await
+            .expect("failed to connect to socket");
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+        let start = std::time::Instant::now();
+        while calls.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+        std::fs::remove_file(&path).unwrap();
+
+        let calls = calls.lock().await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_server_max_concurrent() {
+        #[derive(Clone, Default)]
+        struct SlowHandler {
+            active: Arc<AtomicUsize>,
+            max_active: Arc<AtomicUsize>,
+            handled: Arc<AtomicUsize>,
+        }
+
+        #[async_trait]
+        impl GameStateHandler<serde_json::Value> for SlowHandler {
+            async fn handle(self, _gs: serde_json::Value) -> Result<(), HandlerError> {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_active.fetch_max(active, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                self.handled.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let handler = SlowHandler::default();
+
+        let running = ServerBuilder::from(GSIServer::new(TEST_URI).with_max_concurrent(1))
+            .register(handler.clone())
+            .start()
+            .await
+            .expect("failed to start server");
+        let local_addr = running.local_addr().unwrap();
+        let server = tokio::spawn(running.run_forever());
+
+        let clients: Vec<_> = (0..3)
+            .map(|_| {
+                tokio::spawn(async move {
+                    let mut stream = TcpStream::connect(local_addr).await.unwrap();
+                    stream.write_all(sample_request).await.unwrap();
+                    stream.shutdown().await.unwrap();
+                    let mut response = Vec::new();
+                    stream.read_to_end(&mut response).await.unwrap();
+                })
+            })
+            .collect();
+        for client in clients {
+            client.await.unwrap();
+        }
+
+        let start = std::time::Instant::now();
+        while handler.handled.load(Ordering::SeqCst) < 3 && start.elapsed() < Duration::from_secs(2)
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        assert_eq!(handler.handled.load(Ordering::SeqCst), 3);
+        assert_eq!(handler.max_active.load(Ordering::SeqCst), 1);
+
+        let unlimited = GSIServer::new(TEST_URI).with_max_concurrent(0);
+        assert!(unlimited.concurrency.is_none());
+        assert!(unlimited.acquire_permit().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_server_heartbeat_timeout() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let timeouts = Arc::new(AtomicUsize::new(0));
+        let counter = timeouts.clone();
+
+        let server =
+            GSIServer::new(TEST_URI).with_heartbeat_timeout(Duration::from_millis(50), move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        server.connection.heartbeat.as_ref().unwrap().watch();
+
+        tokio::time::advance(Duration::from_millis(20)).await;
+        task::yield_now().await;
+        assert_eq!(timeouts.load(Ordering::SeqCst), 0);
+
+        // Fires once per period without events, however long it lasts.
+        for _ in 0..10 {
+            tokio::time::advance(Duration::from_millis(15)).await;
+            task::yield_now().await;
+        }
+        assert_eq!(timeouts.load(Ordering::SeqCst), 1);
+
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(sample_request).await.unwrap();
+        let parsed: Option<serde_json::Value> = server
+            .connection
+            .read_state(stream)
+            .await
+            .expect("processing failed");
+        assert!(parsed.is_some());
+
+        tokio::time::advance(Duration::from_millis(20)).await;
+        task::yield_now().await;
+        assert_eq!(timeouts.load(Ordering::SeqCst), 1);
+
+        for _ in 0..10 {
+            tokio::time::advance(Duration::from_millis(15)).await;
+            task::yield_now().await;
+        }
+        assert_eq!(timeouts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_server_run_boxed() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}";
+        let local_addr = free_addr();
+
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let captured = calls.clone();
+        let handler: BoxedHandler<serde_json::Value> = Arc::new(move |gs| {
+            let captured = captured.clone();
+            Box::pin(async move {
+                captured.lock().await.push(gs);
+                Ok(())
+            })
+        });
+
+        let server = tokio::spawn(GSIServer::new(&local_addr.to_string()).run_boxed(handler));
+
+        let start = std::time::Instant::now();
+        let mut stream = connect(local_addr).await;
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        while calls.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let calls = calls.lock().await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_server_run_with() {
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 2}";
+        let local_addr = free_addr();
+
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let server = tokio::spawn(
+            GSIServer::new(&local_addr.to_string())
+                .run_with(calls.clone(), |calls, gs: serde_json::Value| async move {
+                    calls.lock().await.push(gs)
+                }),
+        );
+
+        let start = std::time::Instant::now();
+        let mut stream = connect(local_addr).await;
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        while calls.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let calls = calls.lock().await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["id"], 2);
+    }
+
+    #[test]
+    fn test_peer_display() {
+        let peer = Peer::Tcp(([127, 0, 0, 1], 53000).into());
+        assert_eq!(peer.to_string(), "127.0.0.1:53000");
+
+        #[cfg(unix)]
+        {
+            assert_eq!(Peer::Unix(None).to_string(), "(unnamed)");
+            let named = Peer::Unix(Some(PathBuf::from("/tmp/dota.sock")));
+            assert_eq!(named.to_string(), "/tmp/dota.sock");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_handler_registry() {
+        #[derive(Clone, Default)]
+        struct Collect(Arc<tokio::sync::Mutex<Vec<serde_json::Value>>>);
+
+        #[async_trait]
+        impl GameStateHandler<serde_json::Value> for Collect {
+            async fn handle(self, gs: serde_json::Value) -> Result<(), HandlerError> {
+                self.0.lock().await.push(gs);
+                Ok(())
+            }
+        }
+
+        let local_addr = free_addr();
+
+        let registry = crate::handlers::HandlerRegistry::new();
+        let collect = Collect::default();
+        let id = registry.register(collect.clone());
+        let server = tokio::spawn(
+            GSIServer::new(&local_addr.to_string()).run_with_handler(registry.clone()),
+        );
+
+        let start = std::time::Instant::now();
+        for body in [r#"{"id": 1}"#, r#"{"id": 2}"#] {
+            let mut stream = connect(local_addr).await;
+            let request = format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await.unwrap();
+
+            while collect.0.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            // Only the first event is handled before unregistering.
+            registry.unregister(id);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        server.abort();
+
+        let calls = collect.0.lock().await;
+        assert_eq!(*calls, vec![serde_json::json!({"id": 1})]);
+    }
+
+    #[tokio::test]
+    async fn test_read_request_parts_headers() {
+        let (mut client, mut stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client
+            .write_all(b"POST / HTTP/1.1\r\nContent-Length: 2\r\nX-Match-Id: 7412\r\nX-Tenant: \xff\r\n\r\n{}")
+            .await
+            .unwrap();
+
+        let mut buf = BytesMut::new();
+        let request =
+            read_request_parts(&mut stream, &mut buf, OK.as_bytes(), DEFAULT_MAX_BODY_BYTES)
+                .await
+                .expect("failed to read request");
+        let headers = request.headers;
+
+        assert_eq!(request.body.as_ref(), b"{}");
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("x-match-id"), Some("7412"));
+        assert_eq!(headers.get("X-Tenant"), Some("\u{FFFD}"));
+        assert_eq!(headers.get("Authorization"), None);
+        assert_eq!(
+            headers.iter().map(|(n, _)| n).collect::<Vec<&str>>(),
+            vec!["Content-Length", "X-Match-Id", "X-Tenant"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_run_with_headers_handler() {
+        #[derive(Clone, Default)]
+        struct MatchHandler {
+            calls: Arc<tokio::sync::Mutex<Vec<serde_json::Value>>>,
+        }
+
+        #[async_trait]
+        impl GameStateHandlerWithHeaders<serde_json::Value> for MatchHandler {
+            async fn handle(
+                self,
+                request: RequestContext,
+                gs: serde_json::Value,
+            ) -> Result<(), HandlerError> {
+                self.calls.lock().await.push(serde_json::json!({
+                    "match_id": request.headers().get("X-Match-Id"),
+                    "peer": request.peer().to_string(),
+                    "id": gs["id"],
+                }));
+                Ok(())
+            }
+        }
+
+        let sample_request =
+            b"POST / HTTP/1.1\r\nX-Match-Id: 7412\r\nContent-Length: 9\r\n\r\n{\"id\": 3}";
+        let local_addr = free_addr();
+
+        let handler = MatchHandler::default();
+        let server = GSIServer::new(&local_addr.to_string());
+        let stats = server.stats();
+        let server = tokio::spawn(server.run_with_headers_handler(handler.clone()));
+
+        let start = std::time::Instant::now();
+        let mut stream = connect(local_addr).await;
+        let client_addr = stream.local_addr().unwrap();
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        while handler.calls.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let calls = handler.calls.lock().await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["match_id"], "7412");
+        assert_eq!(calls[0]["peer"], client_addr.to_string());
+        assert_eq!(calls[0]["id"], 3);
+        assert_eq!(stats.connections_by_peer().get(&client_addr.ip()), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_server_route_by_token() {
+        let local_addr = free_addr();
+
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let route = |name: &'static str| -> BoxedHandler<serde_json::Value> {
+            let calls = calls.clone();
+            Arc::new(move |gs| {
+                let calls = calls.clone();
+                Box::pin(async move {
+                    calls.lock().await.push((name, gs["id"].as_u64().unwrap()));
+                    Ok(())
+                })
+            })
+        };
+        let routes = HashMap::from([
+            ("first".to_owned(), route("first")),
+            ("second".to_owned(), route("second")),
+        ]);
+
+        let server = tokio::spawn(
+            GSIServer::new(&local_addr.to_string()).route_by_token(routes, Some(route("fallback"))),
+        );
+
+        let start = std::time::Instant::now();
+        let bodies = [
+            r#"{"id": 1, "auth": {"token": "second"}}"#,
+            r#"{"id": 2, "auth": {"token": "first"}}"#,
+            r#"{"id": 3, "auth": {"token": "unknown"}}"#,
+            r#"{"id": 4}"#,
+        ];
+        for body in bodies {
+            let mut stream = connect(local_addr).await;
+            let request = format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await.unwrap();
+        }
+
+        while calls.lock().await.len() < bodies.len() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        let mut calls = calls.lock().await.clone();
+        calls.sort_by_key(|(_, id)| *id);
+        assert_eq!(
+            calls,
+            vec![
+                ("second", 1),
+                ("first", 2),
+                ("fallback", 3),
+                ("fallback", 4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_reports_handler_errors() {
+        #[derive(Clone)]
+        struct FailingHandler;
+
+        #[async_trait]
+        impl GameStateHandler<serde_json::Value> for FailingHandler {
+            async fn handle(self, _gs: serde_json::Value) -> Result<(), HandlerError> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "websocket closed").into())
+            }
+        }
+
+        let sample_request =
+            b"POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}";
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_errors = errors.clone();
+        let calls = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+
+        let server =
+            GSIServer::new(TEST_URI).on_error(move |e| sink_errors.lock().unwrap().push(e));
+        let running = ServerBuilder::from(server)
+            .register(FailingHandler)
+            .register(RecordHandler {
+                name: "after",
+                calls: calls.clone(),
+            })
+            .start()
+            .await
+            .expect("failed to start server");
+        let local_addr = running.local_addr().unwrap();
+        let server = tokio::spawn(running.run_forever());
+
+        let mut stream = TcpStream::connect(local_addr)
+            .await
+            .expect("failed to connect to address");
+        stream.write_all(sample_request).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let start = std::time::Instant::now();
+        while calls.lock().await.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        server.abort();
+
+        assert_eq!(calls.lock().await.len(), 1);
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            GSIServerError::HandlerError(e) if e.to_string() == "websocket closed"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_server_into_stream() {
+        use tokio_stream::StreamExt;
+
+        let local_addr = free_addr();
+
+        let (errors, mut reported) = mpsc::unbounded_channel();
+        let mut events = Box::pin(
+            GSIServer::new(&local_addr.to_string())
+                .on_error(move |e| {
+                    let _ = errors.send(e);
+                })
+                .into_stream::<serde_json::Value>(),
+        );
+
+        for body in ["{\"id\": 1}", "not json!"] {
+            let request = format!(
+                "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+
+            let mut stream = connect(local_addr).await;
+            stream.write_all(request.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await.unwrap();
+        }
+
+        assert!(matches!(events.next().await, Some(Ok(v)) if v["id"] == 1));
+        assert!(matches!(
+            reported.recv().await,
+            Some(GSIServerError::ParseBodyError { .. })
+        ));
+
+        // Dropping the stream closes the listener without waiting for another connection.
+        drop(events);
+        let start = std::time::Instant::now();
+        while TcpStream::connect(local_addr).await.is_ok() {
+            assert!(
+                start.elapsed() < Duration::from_secs(1),
+                "listener still open"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let mut invalid = Box::pin(GSIServer::new("127.0.0.1").into_stream::<serde_json::Value>());
+        assert!(matches!(
+            invalid.next().await,
+            Some(Err(GSIServerError::InvalidUri(_)))
+        ));
+        assert!(invalid.next().await.is_none());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_process_gzip_encoded() {
+        use std::io::Write;
+
+        let body = format!("{{\"player\": {{\"name\": \"{}\"}}}}", "a".repeat(1000));
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut request = format!(
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            compressed.len()
+        )
+        .into_bytes();
+        request.extend_from_slice(&compressed);
+
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
 
         let result = process(stream).await.expect("processing failed");
-        assert_eq!(result.len(), expected.len());
-        assert_eq!(result.as_ref(), expected);
+        assert_eq!(result.as_ref(), body.as_bytes());
+
+        let (mut client, mut stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        // The compressed body is within the limit, but not the decompressed body.
+        let limit = compressed.len() * 2;
+        assert!(limit < body.len());
+
+        let mut buf = BytesMut::new();
+        let result = read_request(&mut stream, &mut buf, OK.as_bytes(), limit).await;
+        assert!(matches!(result, Err(GSIServerError::DecodeBodyError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_read_request_line() {
+        let (mut client, mut stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let request = read_request_line(&mut stream, None)
+            .await
+            .expect("failed to read request line");
+        assert_eq!(
+            request,
+            RequestLine {
+                method: "GET".to_owned(),
+                path: "/metrics".to_owned(),
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_request_line_bounded() {
+        let timeout = Duration::from_secs(1);
+
+        let (mut client, mut stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        let result = read_request_line(&mut stream, Some(timeout)).await;
+        assert_eq!(result, Err(GSIServerError::ReadTimeout(timeout)));
+
+        let (mut client, mut stream) = tokio::io::duplex(2 * MAX_REQUEST_HEAD_BYTES);
+        let header = format!("X-Padding: {}\r\n", "a".repeat(MAX_REQUEST_HEAD_BYTES));
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        client.write_all(header.as_bytes()).await.unwrap();
+        let result = read_request_line(&mut stream, Some(timeout)).await;
+        assert_eq!(result, Err(GSIServerError::IncompleteHeaders));
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[tokio::test]
+    async fn test_process_gzip_encoded_unsupported() {
+        let request = b"POST / HTTP/1.1\r\nContent-Encoding: gzip\r\nContent-Length: 2\r\n\r\n{}";
+
+        let (mut client, stream) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+        client.write_all(request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = process(stream).await;
+        assert!(matches!(
+            result,
+            Err(GSIServerError::DecodeBodyError(e)) if e.kind() == io::ErrorKind::Unsupported
+        ));
     }
 }
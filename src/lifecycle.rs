@@ -0,0 +1,191 @@
+//! Coarse lifecycle events, derived from transitions between consecutive events sent by Dota.
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+
+use crate::components::players::PlayerActivity;
+use crate::components::DotaGameRulesState;
+use crate::envelope::Envelope;
+
+/// A change in the lifecycle of a game, reported to the callback set with
+/// [`GSIServer::on_lifecycle`](crate::GSIServer::on_lifecycle).
+///
+/// Lifecycle events are derived by comparing each event with the previous one, so they only
+/// fire on change, and never on the first event received by a server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// The game rules state in `map.game_state` changed to
+    /// `DOTA_GAMERULES_STATE_GAME_IN_PROGRESS`, when the horn sounds.
+    MatchStarted,
+    /// The game rules state in `map.game_state` changed to `DOTA_GAMERULES_STATE_POST_GAME`.
+    MatchEnded,
+    /// The player left a game: the activity in `player.activity` changed to `menu`, or the
+    /// player block stopped being sent. Spectators are never in the menu.
+    ReturnedToMenu,
+}
+
+/// The parts of an event that lifecycle events are derived from.
+#[derive(Debug, Clone, PartialEq)]
+struct Phase {
+    in_menu: bool,
+    game_state: Option<DotaGameRulesState>,
+}
+
+impl Phase {
+    /// Read the phase of an event from its envelope.
+    fn from_envelope(envelope: &Envelope) -> Phase {
+        // A missing or empty player block means we are in the menu, like in GameState::mode.
+        let in_menu = match &envelope.player {
+            Some(Value::Object(player)) if !player.is_empty() => match player.get("activity") {
+                Some(Value::String(activity)) => {
+                    PlayerActivity::from(activity.to_owned()) == PlayerActivity::Menu
+                }
+                _ => false,
+            },
+            _ => true,
+        };
+
+        Phase {
+            in_menu,
+            game_state: envelope.map.as_ref().and_then(|m| m.game_state.clone()),
+        }
+    }
+
+    /// The lifecycle events caused by moving from previous to this phase, in the order they
+    /// are reported.
+    fn transitions(&self, previous: &Phase) -> Vec<LifecycleEvent> {
+        let mut transitions = Vec::new();
+        let entered = |state: DotaGameRulesState| {
+            let was_in_state = previous.game_state.as_ref() == Some(&state);
+            !was_in_state && self.game_state.as_ref() == Some(&state)
+        };
+
+        if entered(DotaGameRulesState::InProgress) {
+            transitions.push(LifecycleEvent::MatchStarted);
+        }
+        if entered(DotaGameRulesState::PostGame) {
+            transitions.push(LifecycleEvent::MatchEnded);
+        }
+        if self.in_menu && !previous.in_menu {
+            transitions.push(LifecycleEvent::ReturnedToMenu);
+        }
+
+        transitions
+    }
+}
+
+/// Tracks the phase of the last event received by a server, calling a callback with the
+/// lifecycle events of every transition.
+pub(crate) struct Lifecycle {
+    previous: Mutex<Option<Phase>>,
+    callback: Arc<dyn Fn(LifecycleEvent) + Send + Sync>,
+}
+
+impl Lifecycle {
+    pub(crate) fn new(callback: impl Fn(LifecycleEvent) + Send + Sync + 'static) -> Self {
+        Lifecycle {
+            previous: Mutex::new(None),
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Compare the envelope of a new event with the previous one, calling the callback
+    /// with every lifecycle event between them.
+    pub(crate) fn observe(&self, envelope: &Envelope) {
+        let phase = Phase::from_envelope(envelope);
+
+        let transitions = {
+            let mut previous = self.previous.lock().unwrap_or_else(|e| e.into_inner());
+            let transitions = match previous.as_ref() {
+                Some(previous) => phase.transitions(previous),
+                None => Vec::new(),
+            };
+            *previous = Some(phase);
+            transitions
+        };
+
+        // The lock is released before calling back, so a slow callback doesn't block others.
+        for event in transitions {
+            (self.callback)(event);
+        }
+    }
+}
+
+impl std::fmt::Debug for Lifecycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Lifecycle")
+            .field("previous", &self.previous)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(activity: Option<&str>, game_state: &str) -> Envelope {
+        let player = match activity {
+            Some(activity) => serde_json::json!({ "activity": activity }),
+            None => serde_json::json!({}),
+        };
+
+        serde_json::from_value(serde_json::json!({
+            "player": player,
+            "map": { "game_state": game_state },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lifecycle_transitions() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let lifecycle = Lifecycle::new(move |e| sink.lock().unwrap().push(e));
+
+        let sequence = [
+            event(Some("menu"), "DOTA_GAMERULES_STATE_DISCONNECTED"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_HERO_SELECTION"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_PRE_GAME"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_POST_GAME"),
+            event(Some("playing"), "DOTA_GAMERULES_STATE_POST_GAME"),
+            event(None, "DOTA_GAMERULES_STATE_DISCONNECTED"),
+        ];
+        for body in &sequence {
+            lifecycle.observe(body);
+        }
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                LifecycleEvent::MatchStarted,
+                LifecycleEvent::MatchEnded,
+                LifecycleEvent::ReturnedToMenu,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_first_event_is_baseline() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let lifecycle = Lifecycle::new(move |e| sink.lock().unwrap().push(e));
+
+        // A server started mid-game doesn't report the match starting.
+        lifecycle.observe(&event(
+            Some("playing"),
+            "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS",
+        ));
+        // Abandoning a game returns to the menu without the match ending.
+        lifecycle.observe(&event(
+            Some("menu"),
+            "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS",
+        ));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![LifecycleEvent::ReturnedToMenu]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Commonly used types, re-exported to be glob imported with `use dota::prelude::*;`.
+pub use crate::components::abilities::{Ability, AbilityID, GameAbilities};
+pub use crate::components::buildings::{BuildingInformation, Buildings};
+pub use crate::components::couriers::{Courier, Couriers};
+pub use crate::components::events::{Event, RoshanTracker};
+pub use crate::components::heroes::{GameHeroes, Hero};
+pub use crate::components::items::{GameItems, Item, ItemSlot, Items, Rune};
+pub use crate::components::minimap::{Minimap, MinimapObject, MinimapObjectKind};
+pub use crate::components::players::{GamePlayers, PlayerActivity, PlayerID, PlayerInformation};
+pub use crate::components::scoreboard::{PlayerRow, Scoreboard};
+pub use crate::components::team::Team;
+pub use crate::components::wearables::{GameWearables, Wearables};
+pub use crate::components::{
+    DotaGameRulesState, GameMode, GameState, Map, Position, Provider, TimeOfDay,
+};
+pub use crate::{
+    BoxedHandler, GSIServer, GSIServerError, GameStateHandler, GameStateHandlerWithHeaders,
+    HandlerError, Headers, LifecycleEvent, Peer, RequestContext, RunningServer, ServerBuilder,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Handler;
+
+    #[async_trait::async_trait]
+    impl GameStateHandler<GameState> for Handler {
+        async fn handle(self, gs: GameState) -> Result<(), HandlerError> {
+            let _ = gs.get_hero();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prelude_types() {
+        let json_str = r#"{
+            "provider": {
+                "name": "Dota 2",
+                "appid": 570,
+                "version": 47,
+                "timestamp": 1688514013
+            },
+            "map": {
+                "name": "hero_demo_main",
+                "matchid": "0",
+                "game_time": 5,
+                "clock_time": 4,
+                "daytime": true,
+                "nightstalker_night": false,
+                "game_state": "DOTA_GAMERULES_STATE_GAME_IN_PROGRESS",
+                "paused": false,
+                "win_team": "none",
+                "customgamename": "hero_demo"
+            },
+            "hero": {
+                "id": 42,
+                "name": "npc_dota_hero_skeleton_king"
+            },
+            "items": {
+                "slot0": {"name": "empty"},
+                "teleport0": {"name": "item_tpscroll", "purchaser": 0, "passive": false},
+                "neutral0": {"name": "empty"}
+            }
+        }"#;
+
+        let gs: GameState =
+            serde_json::from_str(json_str).expect("Failed to deserialize GameState");
+        let hero: &Hero = gs.get_hero().expect("missing hero");
+        let items: &Items = gs.get_items().expect("missing items");
+
+        assert_eq!(hero.id, 42);
+        assert!(items.is_inventory_empty());
+        assert!(!items.is_teleport_empty());
+        assert!(gs
+            .get_team_player_hero(&Team::Radiant, &PlayerID::from(0))
+            .is_none());
+        assert!(gs.events().is_empty());
+        assert!(matches!(
+            DotaGameRulesState::from("DOTA_GAMERULES_STATE_GAME_IN_PROGRESS".to_string()),
+            DotaGameRulesState::InProgress
+        ));
+
+        Handler.handle(gs).await.unwrap();
+
+        let _server: GSIServer = GSIServer::new("127.0.0.1:3000");
+        let _error: GSIServerError = GSIServerError::SocketClosed;
+    }
+}
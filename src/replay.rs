@@ -0,0 +1,477 @@
+//! Replay recorded Game State Integration events through a [`GameStateHandler`].
+//!
+//! Recordings are files with one JSON event per line, as written by the `recall` binary or
+//! [`JsonLinesHandler`](crate::handlers::JsonLinesHandler). This allows testing handlers
+//! offline, as if Dota was sending the events.
+//!
+//! Recordings can also be a directory with one event per file, as written by `recall` without
+//! `--single-file`. These are loaded in the order they were recorded with [`load_dir`].
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::{GameStateHandler, HandlerError};
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("failed to read recorded events")]
+    ReadError(#[from] io::Error),
+    #[error("failed to parse recorded event in line {line}")]
+    ParseJSONError {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("handler failed to handle recorded event in line {line}")]
+    HandlerError {
+        line: usize,
+        #[source]
+        source: HandlerError,
+    },
+    #[error("delay before recorded event in line {line} is too long to replay at speed {speed}")]
+    DelayOverflow { line: usize, speed: f32 },
+}
+
+/// Prefix of the files written by `recall`, one per event, like
+/// `DotaGSI_20230704T182013.123.json`.
+pub const RECORDING_FILE_PREFIX: &str = "DotaGSI_";
+
+/// Extension of the files written by `recall`.
+pub const RECORDING_FILE_EXTENSION: &str = "json";
+
+/// The [chrono format](https://docs.rs/chrono/latest/chrono/format/strftime/index.html) of the
+/// UTC timestamp in the files written by `recall`. Unlike the default format, it's valid in
+/// file names on every platform, and sorts in the order events were recorded.
+pub const RECORDING_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3f";
+
+/// The name of a file written by `recall` for an event received at timestamp, formatted with
+/// [`RECORDING_TIMESTAMP_FORMAT`].
+///
+/// Events received in the same millisecond are told apart by counter: the first event has a
+/// counter of 0, which is left out, and the next ones are suffixed like
+/// `DotaGSI_20230704T182013.123-1.json`.
+pub fn recording_file_name<T: fmt::Display>(timestamp: T, counter: u32) -> String {
+    if counter == 0 {
+        format!(
+            "{}{}.{}",
+            RECORDING_FILE_PREFIX, timestamp, RECORDING_FILE_EXTENSION
+        )
+    } else {
+        format!(
+            "{}{}-{}.{}",
+            RECORDING_FILE_PREFIX, timestamp, counter, RECORDING_FILE_EXTENSION
+        )
+    }
+}
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: i128 = 86_400;
+
+/// Days from 1970-01-01 to the given date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i128, month: i128, day: i128) -> i128 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parse a timestamp like `20230704T182013.123456789`, in UTC, into nanoseconds since epoch.
+fn parse_compact_timestamp(s: &str) -> Option<i128> {
+    let (date, time) = s.split_once('T')?;
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+
+    if date.len() != 8
+        || time.len() != 6
+        || fraction.len() > 9
+        || !date
+            .chars()
+            .chain(time.chars())
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let number = |s: &str| s.parse::<i128>().ok();
+    let days = days_from_civil(
+        number(&date[..4])?,
+        number(&date[4..6])?,
+        number(&date[6..])?,
+    );
+    let seconds = number(&time[..2])? * 3600 + number(&time[2..4])? * 60 + number(&time[4..])?;
+    let nanos = format!("{:0<9}", fraction).parse::<i128>().ok()?;
+
+    Some((days * SECONDS_PER_DAY + seconds) * NANOS_PER_SECOND + nanos)
+}
+
+/// Parse an offset like `+02:00` into seconds.
+fn parse_offset(s: &str) -> Option<i128> {
+    let (sign, offset) = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(offset), _) => (1, offset),
+        (_, Some(offset)) => (-1, offset),
+        _ => return None,
+    };
+    let (hours, minutes) = offset.split_once(':')?;
+
+    Some(sign * (hours.parse::<i128>().ok()? * 3600 + minutes.parse::<i128>().ok()? * 60))
+}
+
+/// Parse the name of a file written by `recall` into its timestamp, in nanoseconds since epoch,
+/// and its counter. See [`recording_file_name`].
+///
+/// Files named before [`RECORDING_TIMESTAMP_FORMAT`] was adopted used the local time with its
+/// offset, like `DotaGSI_2023-07-04 18:20:13.123456789 +02:00.json`, or the UTC time with a
+/// `Z` suffix, like `DotaGSI_20230704T182013.123456789Z.json`, and are parsed too.
+fn recording_timestamp(file_name: &str) -> Option<(i128, u32)> {
+    let timestamp = file_name
+        .strip_prefix(RECORDING_FILE_PREFIX)?
+        .strip_suffix(RECORDING_FILE_EXTENSION)?
+        .strip_suffix('.')?;
+
+    if let Some((date, time)) = timestamp.split_once(' ') {
+        let (time, offset) = time.split_once(' ')?;
+        let compact = format!("{}T{}", date, time).replace(['-', ':'], "");
+        let utc = parse_compact_timestamp(&compact)? - parse_offset(offset)? * NANOS_PER_SECOND;
+
+        return Some((utc, 0));
+    }
+
+    let (utc, counter) = match timestamp.split_once('-') {
+        Some((utc, counter)) => (utc, counter.parse::<u32>().ok()?),
+        None => (timestamp, 0),
+    };
+    let utc = utc.strip_suffix('Z').unwrap_or(utc);
+
+    Some((parse_compact_timestamp(utc)?, counter))
+}
+
+/// Paths of the files written by `recall` in dir, in the order they were recorded.
+/// Files with a timestamp that fails to parse come first, sorted by name.
+fn recording_paths(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut recordings = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+
+        if !entry.file_type()?.is_file()
+            || !file_name.starts_with(RECORDING_FILE_PREFIX)
+            || !file_name.ends_with(RECORDING_FILE_EXTENSION)
+        {
+            continue;
+        }
+
+        recordings.push((recording_timestamp(file_name), entry.path()));
+    }
+
+    recordings.sort();
+
+    Ok(recordings.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Load every event recorded by `recall` in dir, one per `DotaGSI_*.json` file, in the order
+/// they were recorded.
+///
+/// Files are read and deserialized into `D` lazily, as the iterator advances. Failing to read
+/// the directory or a file is yielded as an I/O [`serde_json::Error`].
+pub fn load_dir<D: DeserializeOwned>(
+    dir: &Path,
+) -> impl Iterator<Item = Result<D, serde_json::Error>> {
+    let (paths, error) = match recording_paths(dir) {
+        Ok(paths) => (paths, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+
+    error
+        .map(|e| Err(serde_json::Error::io(e)))
+        .into_iter()
+        .chain(paths.into_iter().map(|path| {
+            let contents = fs::read(path).map_err(serde_json::Error::io)?;
+            serde_json::from_slice(&contents)
+        }))
+}
+
+/// Read the `provider.timestamp` of a recorded event, if present.
+fn provider_timestamp(value: &Value) -> Option<u64> {
+    value.get("provider")?.get("timestamp")?.as_u64()
+}
+
+/// Replay every event recorded in the file at path through a handler, in order.
+///
+/// Each line is deserialized into `D` before calling the handler. When speed is greater than 0,
+/// the delay between events reproduces the difference between their `provider.timestamp`,
+/// divided by speed: a speed of 2.0 replays twice as fast as the events were recorded.
+/// A speed of 0 replays events as fast as possible.
+///
+/// Returns the number of events replayed. Replaying stops at the first event that fails to
+/// parse, or that the handler fails to handle, or whose delay is too long to represent, like
+/// with a speed so close to 0 that a single second stretches beyond [`Duration::MAX`].
+pub async fn replay_file<D, H, P>(path: P, handler: H, speed: f32) -> Result<usize, ReplayError>
+where
+    D: DeserializeOwned + std::fmt::Debug + Send + 'static,
+    H: GameStateHandler<D> + Clone,
+    P: AsRef<Path>,
+{
+    let file = File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut last_timestamp: Option<u64> = None;
+    let mut line_number = 0;
+    let mut replayed = 0;
+
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let value: Value =
+            serde_json::from_str(&line).map_err(|e| ReplayError::ParseJSONError {
+                line: line_number,
+                source: e,
+            })?;
+        let timestamp = provider_timestamp(&value);
+
+        if speed > 0.0 {
+            if let (Some(previous), Some(current)) = (last_timestamp, timestamp) {
+                let delta = current.saturating_sub(previous) as f32;
+                let delay = Duration::try_from_secs_f32(delta / speed).map_err(|_| {
+                    ReplayError::DelayOverflow {
+                        line: line_number,
+                        speed,
+                    }
+                })?;
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        if timestamp.is_some() {
+            last_timestamp = timestamp;
+        }
+
+        let parsed: D = serde_json::from_value(value).map_err(|e| ReplayError::ParseJSONError {
+            line: line_number,
+            source: e,
+        })?;
+        handler
+            .clone()
+            .handle(parsed)
+            .await
+            .map_err(|e| ReplayError::HandlerError {
+                line: line_number,
+                source: e,
+            })?;
+        replayed += 1;
+    }
+
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use async_trait::async_trait;
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct CollectHandler {
+        timestamps: Arc<Mutex<Vec<u64>>>,
+    }
+
+    #[async_trait]
+    impl GameStateHandler<Value> for CollectHandler {
+        async fn handle(self, gs: Value) -> Result<(), HandlerError> {
+            let timestamp = provider_timestamp(&gs).ok_or("missing provider timestamp")?;
+            self.timestamps.lock().await.push(timestamp);
+            Ok(())
+        }
+    }
+
+    fn write_recording(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dota-gsi-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).expect("failed to write recording");
+        path
+    }
+
+    const RECORDING: &str = r#"{"provider":{"name":"Dota 2","appid":570,"version":47,"timestamp":1688514013}}
+{"provider":{"name":"Dota 2","appid":570,"version":47,"timestamp":1688514014}}
+
+{"provider":{"name":"Dota 2","appid":570,"version":47,"timestamp":1688514015}}
+"#;
+
+    #[test]
+    fn test_recording_file_name() {
+        assert_eq!(
+            recording_file_name("20230704T182013.123", 0),
+            "DotaGSI_20230704T182013.123.json"
+        );
+        assert_eq!(
+            recording_file_name("20230704T182013.123", 2),
+            "DotaGSI_20230704T182013.123-2.json"
+        );
+    }
+
+    #[test]
+    fn test_recording_timestamp() {
+        assert_eq!(
+            recording_timestamp("DotaGSI_20230704T182013.5.json"),
+            Some((1688494813 * NANOS_PER_SECOND + 500_000_000, 0))
+        );
+        assert_eq!(
+            recording_timestamp("DotaGSI_20230704T182013.500.json"),
+            recording_timestamp("DotaGSI_2023-07-04 20:20:13.5 +02:00.json"),
+        );
+        assert_eq!(
+            recording_timestamp("DotaGSI_2023-07-04 13:20:13 -05:00.json"),
+            Some((1688494813 * NANOS_PER_SECOND, 0))
+        );
+        assert_eq!(
+            recording_timestamp("DotaGSI_20230704T182013.123456789Z.json"),
+            Some((1688494813 * NANOS_PER_SECOND + 123_456_789, 0))
+        );
+        assert_eq!(
+            recording_timestamp("DotaGSI_20230704T182013.123-12.json"),
+            Some((1688494813 * NANOS_PER_SECOND + 123_000_000, 12))
+        );
+        assert_eq!(
+            recording_timestamp("DotaGSI_19691231T235959.json"),
+            Some((-NANOS_PER_SECOND, 0))
+        );
+
+        for name in [
+            "DotaGSI_20230704T1820.json",
+            "DotaGSI_2023-07-04.json",
+            "DotaGSI_20230704T182013.123-x.json",
+            "DotaGSI_20230704T182013.5.txt",
+            "recording.json",
+        ] {
+            assert_eq!(recording_timestamp(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("dota-gsi-load-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let event = |timestamp: u64| {
+            format!(
+                r#"{{"provider":{{"name":"Dota 2","appid":570,"version":47,"timestamp":{}}}}}"#,
+                timestamp
+            )
+        };
+        // Written out of order, with a legacy name recorded between the others.
+        let files = [
+            ("DotaGSI_20230704T182015.100-1.json", event(4)),
+            ("DotaGSI_20230704T182015.100.json", event(3)),
+            ("DotaGSI_20230704T182013.999.json", event(1)),
+            ("DotaGSI_2023-07-04 20:20:14.5 +02:00.json", event(2)),
+            ("notes.json", "not an event".to_owned()),
+        ];
+        for (name, contents) in &files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+
+        let timestamps: Vec<u64> = load_dir::<Value>(&dir)
+            .map(|value| provider_timestamp(&value.expect("failed to load event")).unwrap())
+            .collect();
+        assert_eq!(timestamps, vec![1, 2, 3, 4]);
+
+        std::fs::write(dir.join("DotaGSI_20230704T182016.000.json"), "not json").unwrap();
+        let loaded: Vec<Result<Value, serde_json::Error>> = load_dir(&dir).collect();
+        assert_eq!(loaded.len(), 5);
+        assert!(loaded[4].is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let missing: Vec<Result<Value, serde_json::Error>> = load_dir(&dir).collect();
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].as_ref().unwrap_err().is_io());
+    }
+
+    #[tokio::test]
+    async fn test_replay_file() {
+        let path = write_recording("replay", RECORDING);
+        let handler = CollectHandler::default();
+
+        let replayed = replay_file(&path, handler.clone(), 0.0)
+            .await
+            .expect("failed to replay");
+
+        assert_eq!(replayed, 3);
+        assert_eq!(
+            *handler.timestamps.lock().await,
+            vec![1688514013, 1688514014, 1688514015]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_file_honors_timestamps() {
+        let path = write_recording("replay-timed", RECORDING);
+        let handler = CollectHandler::default();
+        let start = Instant::now();
+
+        replay_file(&path, handler, 20.0)
+            .await
+            .expect("failed to replay");
+
+        // Two deltas of 1 second replayed at 20x.
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_file_delay_overflow() {
+        let path = write_recording("replay-overflow", RECORDING);
+        let handler = CollectHandler::default();
+
+        let result = replay_file(&path, handler.clone(), f32::MIN_POSITIVE).await;
+
+        assert!(matches!(
+            result,
+            Err(ReplayError::DelayOverflow { line: 2, .. })
+        ));
+        assert_eq!(*handler.timestamps.lock().await, vec![1688514013]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_file_invalid_line() {
+        let path = write_recording(
+            "replay-invalid",
+            "{\"provider\":{\"timestamp\":1}}\nnot json\n",
+        );
+
+        let result = replay_file(&path, CollectHandler::default(), 0.0).await;
+
+        assert!(matches!(
+            result,
+            Err(ReplayError::ParseJSONError { line: 2, .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
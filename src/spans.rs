@@ -0,0 +1,100 @@
+//! Spans around each connection and its parse and handle phases.
+//!
+//! With the `tracing` feature, connections are processed inside a `connection` span carrying
+//! the peer address, with nested `parse` and `handle` spans, so a slow handler can be
+//! correlated with the event it was handling. Without it, these are no-ops.
+use std::future::Future;
+
+use serde::de::DeserializeOwned;
+
+use crate::Peer;
+
+/// Spawn the task processing a connection accepted from peer.
+#[cfg(feature = "tracing")]
+pub(crate) fn spawn_connection<F>(peer: &Peer, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    use tracing::Instrument;
+
+    tokio::spawn(task.instrument(tracing::info_span!("connection", peer = %peer)));
+}
+
+/// Spawn the task processing a connection accepted from peer.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn spawn_connection<F>(_peer: &Peer, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(task);
+}
+
+/// Parse a request body as JSON.
+#[cfg(feature = "tracing")]
+pub(crate) fn parse<D>(body: &[u8]) -> serde_json::Result<D>
+where
+    D: DeserializeOwned,
+{
+    let span = tracing::debug_span!(
+        "parse",
+        content_length = body.len(),
+        parse_duration = tracing::field::Empty
+    );
+    let _entered = span.enter();
+
+    let start = std::time::Instant::now();
+    let parsed = serde_json::from_slice(body);
+    span.record("parse_duration", tracing::field::debug(start.elapsed()));
+
+    parsed
+}
+
+/// Parse a request body as JSON.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn parse<D>(body: &[u8]) -> serde_json::Result<D>
+where
+    D: DeserializeOwned,
+{
+    serde_json::from_slice(body)
+}
+
+/// Run a handler on a parsed event.
+#[cfg(feature = "tracing")]
+pub(crate) async fn handle<F>(handling: F) -> F::Output
+where
+    F: Future,
+{
+    use tracing::Instrument;
+
+    handling.instrument(tracing::debug_span!("handle")).await
+}
+
+/// Run a handler on a parsed event.
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn handle<F>(handling: F) -> F::Output
+where
+    F: Future,
+{
+    handling.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spans_are_transparent() {
+        let parsed: serde_json::Value = parse(br#"{"a": 1}"#).expect("failed to parse");
+        assert_eq!(parsed["a"], 1);
+        assert!(parse::<serde_json::Value>(b"{").is_err());
+
+        assert_eq!(handle(async { 42 }).await, 42);
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let peer = Peer::Tcp(([127, 0, 0, 1], 53000).into());
+        spawn_connection(&peer, async move {
+            sender.send(()).unwrap();
+        });
+        receiver.await.expect("task did not run");
+    }
+}
@@ -0,0 +1,273 @@
+//! Lifetime statistics of a [`GSIServer`](crate::GSIServer).
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Latencies are counted in buckets by the power of two of their microseconds: bucket `i`
+/// holds latencies below `2^i` microseconds, and the last one everything from ~18 minutes.
+const LATENCY_BUCKETS: usize = 32;
+
+/// Maximum number of peers whose connections are counted, so a server reachable by many hosts
+/// doesn't grow its statistics without bound. Dota usually runs on the same host as the server.
+const MAX_COUNTED_PEERS: usize = 64;
+
+/// Statistics of a server, updated while it runs.
+///
+/// Get them with [`GSIServer::stats`](crate::GSIServer::stats) before running the server, and
+/// read them concurrently with [`ServerStats::snapshot`]. Reads and updates are lock-free,
+/// except for the connections counted by peer, which take a short lock once per connection.
+#[derive(Debug)]
+pub struct ServerStats {
+    events: AtomicU64,
+    parse_errors: AtomicU64,
+    bytes_received: AtomicU64,
+    created: Instant,
+    /// Nanoseconds from created to the last event, plus one, or zero if there are no events.
+    last_event: AtomicU64,
+    latencies: [AtomicU64; LATENCY_BUCKETS],
+    max_latency_micros: AtomicU64,
+    connections_by_peer: Mutex<HashMap<IpAddr, u64>>,
+}
+
+impl Default for ServerStats {
+    fn default() -> Self {
+        ServerStats {
+            events: AtomicU64::new(0),
+            parse_errors: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            created: Instant::now(),
+            last_event: AtomicU64::new(0),
+            latencies: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_latency_micros: AtomicU64::new(0),
+            connections_by_peer: Mutex::default(),
+        }
+    }
+}
+
+impl ServerStats {
+    /// Record an event with a body of the given size was just received.
+    pub(crate) fn record_event(&self, bytes: usize) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+
+        let since_created = self.created.elapsed().as_nanos() as u64;
+        self.last_event
+            .store(since_created.saturating_add(1), Ordering::Relaxed);
+    }
+
+    /// Record a TCP connection was accepted from the given peer.
+    /// Once [`MAX_COUNTED_PEERS`] peers are counted, connections from new peers are not.
+    pub(crate) fn record_connection(&self, peer: &SocketAddr) {
+        let mut connections = self
+            .connections_by_peer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let counted = connections.len();
+        match connections.get_mut(&peer.ip()) {
+            Some(count) => *count += 1,
+            None if counted < MAX_COUNTED_PEERS => {
+                connections.insert(peer.ip(), 1);
+            }
+            None => {}
+        }
+    }
+
+    /// Record an event failed to parse.
+    pub(crate) fn record_parse_error(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an event took the given time from accepting its connection to being handled.
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+
+        self.latencies[bucket.min(LATENCY_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+        self.max_latency_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// The latency below which the given fraction of events were handled, from accepting their
+    /// connection to the handler finishing, or None if no events were handled yet.
+    ///
+    /// Latencies are approximated by the next power of two of their microseconds, so this is an
+    /// upper bound at most twice the actual value, and never above [`ServerStats::max_latency`].
+    pub fn latency_quantile(&self, quantile: f64) -> Option<Duration> {
+        let counts: Vec<u64> = self
+            .latencies
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let max_latency = self.max_latency_micros.load(Ordering::Relaxed);
+        let mut seen = 0;
+
+        for (bucket, count) in counts.into_iter().enumerate() {
+            seen += count;
+            if seen >= rank && bucket < LATENCY_BUCKETS - 1 {
+                let upper_bound = 1u64 << bucket;
+                return Some(Duration::from_micros(upper_bound.min(max_latency)));
+            }
+        }
+
+        Some(Duration::from_micros(max_latency))
+    }
+
+    /// The latency below which 99% of events were handled. See [`ServerStats::latency_quantile`].
+    ///
+    /// With a `throttle` of 0.1 in the configuration file, Dota sends up to 10 events per second,
+    /// so handlers keep up as long as this stays below 100ms.
+    pub fn p99_latency(&self) -> Option<Duration> {
+        self.latency_quantile(0.99)
+    }
+
+    /// The highest latency of a handled event, or None if no events were handled yet.
+    pub fn max_latency(&self) -> Option<Duration> {
+        if self
+            .latencies
+            .iter()
+            .all(|c| c.load(Ordering::Relaxed) == 0)
+        {
+            return None;
+        }
+
+        Some(Duration::from_micros(
+            self.max_latency_micros.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// Number of TCP connections accepted from each peer. Peers are identified by their IP
+    /// address alone, as every connection comes from a new port. Connections to a Unix domain
+    /// socket aren't counted, as their peers are usually unnamed.
+    ///
+    /// Only the first 64 peers to connect are counted.
+    pub fn connections_by_peer(&self) -> HashMap<IpAddr, u64> {
+        self.connections_by_peer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Copy the current statistics.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let last_event = match self.last_event.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(self.created + Duration::from_nanos(nanos - 1)),
+        };
+
+        StatsSnapshot {
+            events: self.events.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            last_event,
+        }
+    }
+}
+
+/// A copy of [`ServerStats`] at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Events received, whether they parsed or not.
+    pub events: u64,
+    /// Events that failed to parse.
+    pub parse_errors: u64,
+    /// Bytes received in the body of every event.
+    pub bytes_received: u64,
+    /// When the last event was received, if any.
+    pub last_event: Option<Instant>,
+}
+
+impl StatsSnapshot {
+    /// Time elapsed since the last event was received, if any.
+    pub fn since_last_event(&self) -> Option<Duration> {
+        self.last_event.map(|i| i.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_stats_snapshot() {
+        let stats = ServerStats::default();
+        let empty = stats.snapshot();
+
+        assert_eq!(empty.events, 0);
+        assert_eq!(empty.last_event, None);
+        assert_eq!(empty.since_last_event(), None);
+
+        let before = Instant::now();
+        stats.record_event(100);
+        stats.record_event(50);
+        stats.record_parse_error();
+        stats.record_connection(&([127, 0, 0, 1], 53000).into());
+        stats.record_connection(&([127, 0, 0, 1], 53001).into());
+        let snapshot = stats.snapshot();
+
+        assert_eq!(snapshot.events, 2);
+        assert_eq!(snapshot.parse_errors, 1);
+        assert_eq!(snapshot.bytes_received, 150);
+        assert!(snapshot.last_event.unwrap() >= before);
+        assert!(snapshot.since_last_event().unwrap() < Duration::from_secs(1));
+        assert_eq!(
+            stats.connections_by_peer(),
+            HashMap::from([(IpAddr::from([127, 0, 0, 1]), 2)])
+        );
+    }
+
+    #[test]
+    fn test_server_stats_connections_by_peer_are_bounded() {
+        let stats = ServerStats::default();
+
+        for i in 0..=MAX_COUNTED_PEERS {
+            stats.record_connection(&([10, 0, 0, i as u8], 53000).into());
+        }
+        stats.record_connection(&([10, 0, 0, 0], 53001).into());
+
+        let connections = stats.connections_by_peer();
+        assert_eq!(connections.len(), MAX_COUNTED_PEERS);
+        assert_eq!(connections.get(&IpAddr::from([10, 0, 0, 0])), Some(&2));
+        assert_eq!(
+            connections.get(&IpAddr::from([10, 0, 0, MAX_COUNTED_PEERS as u8])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_server_stats_latency() {
+        let stats = ServerStats::default();
+
+        assert_eq!(stats.p99_latency(), None);
+        assert_eq!(stats.max_latency(), None);
+
+        for _ in 0..98 {
+            stats.record_latency(Duration::from_micros(900));
+        }
+        stats.record_latency(Duration::from_millis(5));
+        stats.record_latency(Duration::from_millis(40));
+
+        // 900us falls in the bucket below 1024us.
+        assert_eq!(
+            stats.latency_quantile(0.5),
+            Some(Duration::from_micros(1024))
+        );
+        // 5ms falls in the bucket below 8192us.
+        assert_eq!(stats.p99_latency(), Some(Duration::from_micros(8192)));
+        // 40ms falls in the bucket below 65536us, but no latency was that high.
+        assert_eq!(stats.latency_quantile(1.0), Some(Duration::from_millis(40)));
+        assert_eq!(stats.max_latency(), Some(Duration::from_millis(40)));
+
+        stats.record_latency(Duration::from_secs(3600));
+        assert_eq!(stats.max_latency(), Some(Duration::from_secs(3600)));
+        assert_eq!(stats.latency_quantile(1.0), Some(Duration::from_secs(3600)));
+    }
+}
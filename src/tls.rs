@@ -0,0 +1,147 @@
+//! TLS termination for a [`GSIServer`](crate::GSIServer), enabled with the `tls` feature.
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{crypto, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::GSIServerError;
+
+/// Accepts TLS connections with a certificate and private key loaded from PEM files.
+#[derive(Clone)]
+pub(crate) struct Tls {
+    acceptor: TlsAcceptor,
+}
+
+impl Tls {
+    /// Load the certificate chain in cert_path and the private key in key_path.
+    pub(crate) fn load(cert_path: &Path, key_path: &Path) -> Result<Self, GSIServerError> {
+        let certs = CertificateDer::pem_file_iter(cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(invalid_data)?;
+        let key = PrivateKeyDer::from_pem_file(key_path).map_err(invalid_data)?;
+
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+                .map_err(invalid_data)?;
+
+        Ok(Tls {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Complete the TLS handshake on an accepted connection.
+    pub(crate) async fn accept<S>(&self, socket: S) -> Result<TlsStream<S>, GSIServerError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.acceptor
+            .accept(socket)
+            .await
+            .map_err(GSIServerError::TlsError)
+    }
+}
+
+impl fmt::Debug for Tls {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tls(..)")
+    }
+}
+
+fn invalid_data<E>(e: E) -> GSIServerError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    GSIServerError::TlsError(io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::TlsConnector;
+
+    use crate::process;
+
+    const DUPLEX_CAPACITY_BYTES: usize = 64 * 1024;
+
+    const CERT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/localhost.crt");
+    const KEY_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/localhost.key");
+
+    fn connector() -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(CERT_PATH).unwrap() {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+
+        TlsConnector::from(Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn test_tls_accept() {
+        let tls = Tls::load(Path::new(CERT_PATH), Path::new(KEY_PATH)).expect("failed to load");
+        let (client, server) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        let client = tokio::spawn(async move {
+            let name = ServerName::try_from("localhost").unwrap();
+            let mut stream = connector().connect(name, client).await.unwrap();
+            stream
+                .write_all(b"POST / HTTP/1.1\r\nContent-Length: 8\r\n\r\n{\"a\": 1}")
+                .await
+                .unwrap();
+            stream.shutdown().await.unwrap();
+
+            let mut response = Vec::new();
+            let _ = stream.read_to_end(&mut response).await;
+            response
+        });
+
+        let stream = tls.accept(server).await.expect("handshake failed");
+        let body = process(stream).await.expect("processing failed");
+
+        assert_eq!(body.as_ref(), b"{\"a\": 1}");
+        assert!(client.await.unwrap().starts_with(b"HTTP/1.1 200 OK"));
+    }
+
+    #[tokio::test]
+    async fn test_tls_handshake_error() {
+        let tls = Tls::load(Path::new(CERT_PATH), Path::new(KEY_PATH)).expect("failed to load");
+        let (mut client, server) = tokio::io::duplex(DUPLEX_CAPACITY_BYTES);
+
+        client
+            .write_all(b"POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\n")
+            .await
+            .unwrap();
+        client.shutdown().await.unwrap();
+
+        assert!(matches!(
+            tls.accept(server).await,
+            Err(GSIServerError::TlsError(_))
+        ));
+    }
+
+    #[test]
+    fn test_tls_load_missing_files() {
+        let result = Tls::load(Path::new("missing.crt"), Path::new(KEY_PATH));
+
+        assert!(matches!(result, Err(GSIServerError::TlsError(_))));
+    }
+}